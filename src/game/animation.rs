@@ -0,0 +1,245 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::engine::{Engine, Phase};
+
+// How many particles burst out of every cleared cell.
+const PARTICLES_PER_CELL: usize = 3;
+// How fast the particles fall, in cells per frame per frame.
+const PARTICLE_GRAVITY: f32 = 0.02;
+// How far the board shakes at most for a Tetris, in cells.
+const SHAKE_STRENGTH: f32 = 0.3;
+
+/// How long every effect lasts, in frames. Everything you leave out has its default value.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnimationSettings {
+    // Turns every effect off at once.
+    pub enabled: bool,
+    // The cleared rows flash red, the more rows the redder.
+    pub row_flash: u32,
+    // The rows above the cleared ones slide down instead of jumping.
+    pub row_collapse: u32,
+    // Every cleared cell bursts into a few particles.
+    pub particles: u32,
+    // The board shakes on a Tetris.
+    pub screen_shake: u32,
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            row_flash: 12,
+            row_collapse: 6,
+            particles: 45,
+            screen_shake: 20,
+        }
+    }
+}
+
+/// A single block flying off the board, positions are in cells of the visible board.
+#[derive(Clone, Debug)]
+pub struct Particle {
+    pub x: f32,
+    pub y: f32,
+    dx: f32,
+    dy: f32,
+    pub color: (u8, u8, u8),
+}
+
+#[derive(Clone, Debug)]
+pub enum Effect {
+    // The board rows that were cleared.
+    RowFlash { rows: Vec<usize> },
+    // The board rows that were deleted, the rows above them are still falling into place.
+    RowCollapse { rows: Vec<usize> },
+    Particles { particles: Vec<Particle> },
+    // How far the board is moved this frame, in cells.
+    ScreenShake { offset: (f32, f32) },
+}
+
+/// An effect and how many more frames it is shown for.
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub effect: Effect,
+    pub frames: u32,
+    pub duration: u32,
+}
+
+impl Animation {
+    /// How much of the animation is still left, from 1.0 at the start down to 0.0 at the end.
+    pub fn remaining(&self) -> f32 {
+        self.frames as f32 / self.duration as f32
+    }
+}
+
+/// The queue of effects that are playing right now.
+/// They are started from the events of the engine and advanced once per frame, just like the engine,
+/// so they never hold up the game or the input.
+#[derive(Clone, Debug, Default)]
+pub struct Animations {
+    pub settings: AnimationSettings,
+    pub active: Vec<Animation>,
+    // The rows of a line clear whose rows above have not fallen yet, because the line clear delay is still running.
+    pending_collapse: Option<Vec<usize>>,
+}
+
+impl Animations {
+    pub fn new(settings: AnimationSettings) -> Self {
+        Self {
+            settings,
+            active: Vec::new(),
+            pending_collapse: None,
+        }
+    }
+
+    /// Adds an effect to the queue, unless it is turned off.
+    fn push(&mut self, effect: Effect, duration: u32) {
+        if !self.settings.enabled || duration == 0 {
+            return;
+        }
+
+        self.active.push(Animation {
+            effect,
+            frames: duration,
+            duration,
+        });
+    }
+
+    /// Starts the effects for whatever the engine did in the last frame, has to be called after every tick.
+    /// This takes the cleared rows out of the engine.
+    pub fn watch(&mut self, engine: &mut Engine) {
+        let clearing = matches!(engine.phase, Phase::LineClear { .. });
+
+        // Once the line clear delay is over the rows above the cleared ones fall down.
+        if !clearing {
+            if let Some(rows) = self.pending_collapse.take() {
+                self.push(Effect::RowCollapse { rows }, self.settings.row_collapse);
+            }
+        }
+
+        if engine.cleared_rows.is_empty() {
+            return;
+        }
+
+        let rows = std::mem::take(&mut engine.cleared_rows);
+        let colors = std::mem::take(&mut engine.cleared_colors);
+        let buffer = engine.board.buffer;
+
+        self.push(
+            Effect::RowFlash { rows: rows.clone() },
+            self.settings.row_flash,
+        );
+
+        let mut rng = rand::thread_rng();
+
+        let particles = rows
+            .iter()
+            .zip(&colors)
+            .filter(|(&y, _)| y >= buffer)
+            .flat_map(|(&y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(x, &color)| (y - buffer, x, color))
+            })
+            .flat_map(|(y, x, color)| {
+                (0..PARTICLES_PER_CELL)
+                    .map(|_| Particle {
+                        x: x as f32 + 0.5,
+                        y: y as f32 + 0.5,
+                        dx: rng.gen_range(-0.15..0.15),
+                        dy: rng.gen_range(-0.35..0.0),
+                        color,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        self.push(Effect::Particles { particles }, self.settings.particles);
+
+        if rows.len() >= 4 {
+            self.push(
+                Effect::ScreenShake { offset: (0.0, 0.0) },
+                self.settings.screen_shake,
+            );
+        }
+
+        if clearing {
+            self.pending_collapse = Some(rows);
+        } else {
+            self.push(Effect::RowCollapse { rows }, self.settings.row_collapse);
+        }
+    }
+
+    /// Advances every effect by a frame and drops the ones that are over.
+    pub fn tick(&mut self) {
+        let mut rng = rand::thread_rng();
+
+        for animation in &mut self.active {
+            animation.frames = animation.frames.saturating_sub(1);
+
+            let remaining = animation.remaining();
+
+            match &mut animation.effect {
+                Effect::Particles { particles } => {
+                    for particle in particles {
+                        particle.x += particle.dx;
+                        particle.y += particle.dy;
+                        particle.dy += PARTICLE_GRAVITY;
+                    }
+                }
+                Effect::ScreenShake { offset } => {
+                    let strength = SHAKE_STRENGTH * remaining;
+
+                    *offset = (
+                        rng.gen_range(-1.0..=1.0) * strength,
+                        rng.gen_range(-1.0..=1.0) * strength,
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        self.active.retain(|animation| animation.frames > 0);
+    }
+
+    /// Stops every effect, for a new game.
+    pub fn clear(&mut self) {
+        self.active.clear();
+        self.pending_collapse = None;
+    }
+
+    /// How far the board is moved by screen shakes right now, in cells.
+    pub fn shake_offset(&self) -> (f32, f32) {
+        self.active
+            .iter()
+            .fold((0.0, 0.0), |(x, y), animation| match animation.effect {
+                Effect::ScreenShake { offset } => (x + offset.0, y + offset.1),
+                _ => (x, y),
+            })
+    }
+
+    /// How many cells above its place the board row is drawn, while it is still falling after a line clear.
+    pub fn row_offset(&self, y: usize) -> f32 {
+        self.active
+            .iter()
+            .map(|animation| match &animation.effect {
+                Effect::RowCollapse { rows } => {
+                    fall_distance(rows, y) as f32 * animation.remaining()
+                }
+                _ => 0.0,
+            })
+            .sum()
+    }
+}
+
+/// How many rows the row that is now at `y` fell, when the cleared rows were deleted.
+/// Every row falls by the amount of cleared rows that were below it.
+fn fall_distance(cleared: &[usize], y: usize) -> usize {
+    (y.saturating_sub(cleared.len())..=y)
+        .filter(|original| !cleared.contains(original))
+        .map(|original| (original, cleared.iter().filter(|&&r| r > original).count()))
+        .find(|&(original, falls)| original + falls == y)
+        .map_or(0, |(_, falls)| falls)
+}
@@ -1,23 +1,47 @@
 use std::fmt;
 
+// The size of a standard Tetris board, the rotation systems place their pieces for this width.
+pub const DEFAULT_WIDTH: usize = 10;
+pub const DEFAULT_HEIGHT: usize = 20;
+
+// The smallest board still fits the I piece lying down.
+pub const MIN_SIZE: usize = 4;
+// Every row is a 64 bit mask, so this is as wide as it gets.
+pub const MAX_WIDTH: usize = 64;
+pub const MAX_HEIGHT: usize = 100;
+
+// The hidden rows above the visible board, pieces spawn in the lowest two of them.
+// Blocks can sit up here, but locking a piece up here ends the game, see `Engine::lock_piece`.
+pub const BUFFER_HEIGHT: usize = 20;
+
+/// The locked blocks of the playfield, the falling piece is never part of this.
 #[derive(Clone)]
 pub struct Board {
-    // This is to look if the coordinates are populated,
-    // '#' if a piece is there, ' ' if not.
-    pub board: [[char; 10]; 20],
+    pub width: usize,
+    // The visible rows, the hidden buffer rows come on top of these.
+    pub height: usize,
+    // How many hidden rows there are above the visible ones.
+    // Row 0 is the top of the buffer, the first visible row is row `buffer`.
+    pub buffer: usize,
+    // Every row is stored as a bitmask, bit X is set if column X is populated.
+    // This makes collision checks and line clears simple bit operations.
+    pub rows: Vec<u64>,
     // This stores the color information of the coordinates separately,
     // empty is White, else it's the piece's color.
-    pub color: [[(u8, u8, u8); 10]; 20],
+    // All rows are in one flat list, row after row, so cloning a board only copies two lists.
+    color: Vec<(u8, u8, u8)>,
+    // A row with every column populated.
+    full_row: u64,
 }
 
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for x in self.board {
-            for y in x {
-                if y == ' ' {
-                    write!(f, "-")?;
+        for y in self.visible_rows() {
+            for x in 0..self.width {
+                if self.is_occupied(y, x) {
+                    write!(f, "#")?;
                 } else {
-                    write!(f, "{}", y)?;
+                    write!(f, "-")?;
                 }
             }
             writeln!(f)?;
@@ -27,10 +51,202 @@ impl fmt::Display for Board {
 }
 
 impl Board {
-    pub fn new() -> Self {
+    /// An empty board of the given size, the size is kept between `MIN_SIZE` and the maximums.
+    /// The hidden buffer is added on top of the visible height.
+    pub fn new(width: usize, height: usize) -> Self {
+        let width = width.clamp(MIN_SIZE, MAX_WIDTH);
+        let height = height.clamp(MIN_SIZE, MAX_HEIGHT);
+        let total_height = height + BUFFER_HEIGHT;
+
         Self {
-            board: [[' '; 10]; 20],
-            color: [[(255, 255, 255); 10]; 20],
+            width,
+            height,
+            buffer: BUFFER_HEIGHT,
+            rows: vec![0; total_height],
+            color: vec![(255, 255, 255); width * total_height],
+            full_row: u64::MAX >> (MAX_WIDTH - width),
+        }
+    }
+
+    /// How many rows there are, hidden and visible.
+    pub fn total_height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The indices of the visible rows, top to bottom.
+    pub fn visible_rows(&self) -> std::ops::Range<usize> {
+        self.buffer..self.total_height()
+    }
+
+    /// Checks if the row is part of the hidden buffer above the visible board.
+    pub fn is_hidden(&self, y: usize) -> bool {
+        y < self.buffer
+    }
+
+    /// Checks if the coordinate is populated.
+    pub fn is_occupied(&self, y: usize, x: usize) -> bool {
+        self.rows[y] & (1 << x) != 0
+    }
+
+    /// The color of the coordinate, White if it is empty.
+    pub fn color(&self, y: usize, x: usize) -> (u8, u8, u8) {
+        self.color[y * self.width + x]
+    }
+
+    /// The colors of every coordinate of the row, from left to right.
+    pub fn row_colors(&self, y: usize) -> &[(u8, u8, u8)] {
+        &self.color[y * self.width..(y + 1) * self.width]
+    }
+
+    /// Populates the coordinate with a block of the given color.
+    pub fn fill(&mut self, y: usize, x: usize, color: (u8, u8, u8)) {
+        self.rows[y] |= 1 << x;
+        self.color[y * self.width + x] = color;
+    }
+
+    /// Empties the coordinate again.
+    pub fn empty(&mut self, y: usize, x: usize) {
+        self.rows[y] &= !(1 << x);
+        self.color[y * self.width + x] = (255, 255, 255);
+    }
+
+    /// Empties the whole row.
+    fn empty_row(&mut self, y: usize) {
+        self.rows[y] = 0;
+        self.color[y * self.width..(y + 1) * self.width].fill((255, 255, 255));
+    }
+
+    /// Checks if any of the blocks are out of bounds or already populated.
+    pub fn collides(&self, blocks: &[(usize, usize)]) -> bool {
+        blocks.iter().any(|&(y, x)| {
+            y >= self.total_height() || x >= self.width || self.rows[y] & (1 << x) != 0
+        })
+    }
+
+    /// Locks the blocks of a piece into the board.
+    pub fn lock(&mut self, blocks: &[(usize, usize)], color: (u8, u8, u8)) {
+        for &(y, x) in blocks {
+            self.fill(y, x, color);
+        }
+    }
+
+    /// Checks if anything is placed in the given row.
+    pub fn row_is_empty(&self, y: usize) -> bool {
+        self.rows[y] == 0
+    }
+
+    /// Checks if nothing at all is placed on the board.
+    pub fn is_empty(&self) -> bool {
+        self.rows.iter().all(|&row| row == 0)
+    }
+
+    /// Gets the indices of all the "full" rows, top to bottom.
+    pub fn full_rows(&self) -> Vec<usize> {
+        (0..self.total_height())
+            .filter(|&y| self.rows[y] == self.full_row)
+            .collect()
+    }
+
+    /// Deletes all the "full" rows and moves the rows above them down.
+    /// Returns the indices of the deleted rows.
+    pub fn clear_full_rows(&mut self) -> Vec<usize> {
+        let full_rows = self.full_rows();
+
+        self.collapse_rows(&full_rows);
+
+        full_rows
+    }
+
+    /// Empties the given rows, but leaves the rows above them hanging where they are.
+    pub fn empty_rows(&mut self, rows: &[usize]) {
+        for &y in rows {
+            self.empty_row(y);
+        }
+    }
+
+    /// Deletes the given rows and moves the rows above them down.
+    pub fn collapse_rows(&mut self, rows: &[usize]) {
+        if rows.is_empty() {
+            return;
+        }
+
+        // We go from the bottom up and move every row that stays down to the next free row,
+        // the rows left over at the top are new, empty rows.
+        let mut target = self.total_height();
+
+        for y in (0..self.total_height()).rev() {
+            if !rows.contains(&y) {
+                target -= 1;
+                self.rows[target] = self.rows[y];
+                self.color
+                    .copy_within(y * self.width..(y + 1) * self.width, target * self.width);
+            }
+        }
+
+        for y in 0..target {
+            self.empty_row(y);
+        }
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(DEFAULT_WIDTH, DEFAULT_HEIGHT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLOR: (u8, u8, u8) = (0, 255, 255);
+
+    fn fill_row(board: &mut Board, y: usize) {
+        for x in 0..board.width {
+            board.fill(y, x, COLOR);
         }
     }
+
+    #[test]
+    fn full_rows_are_cleared_and_the_rows_above_fall() {
+        let mut board = Board::default();
+        let bottom = board.total_height() - 1;
+
+        fill_row(&mut board, bottom);
+        fill_row(&mut board, bottom - 2);
+        board.fill(bottom - 1, 0, COLOR);
+        board.fill(bottom - 3, 5, COLOR);
+
+        assert_eq!(board.clear_full_rows(), vec![bottom - 2, bottom]);
+
+        assert!(board.is_occupied(bottom, 0));
+        assert!(board.is_occupied(bottom - 1, 5));
+        assert_eq!(
+            board.rows.iter().map(|row| row.count_ones()).sum::<u32>(),
+            2
+        );
+        assert!(board.full_rows().is_empty());
+    }
+
+    #[test]
+    fn emptied_rows_leave_the_rows_above_hanging() {
+        let mut board = Board::default();
+        let bottom = board.total_height() - 1;
+
+        fill_row(&mut board, bottom);
+        board.fill(bottom - 1, 3, COLOR);
+
+        let rows = board.full_rows();
+        board.empty_rows(&rows);
+
+        assert!(board.row_is_empty(bottom));
+        assert!(board.is_occupied(bottom - 1, 3));
+
+        board.collapse_rows(&rows);
+
+        assert!(board.is_occupied(bottom, 3));
+        assert!(board.row_is_empty(bottom - 1));
+        assert_eq!(board.color(bottom, 3), COLOR);
+        assert_eq!(board.color(bottom - 1, 3), (255, 255, 255));
+    }
 }
@@ -0,0 +1,167 @@
+use super::board::Board;
+use super::pieces::{Piece, PieceType};
+
+/// Whether the last piece was spun into place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TSpin {
+    None,
+    // A T-Spin where only one of the corners the T points at is filled.
+    Mini,
+    Full,
+}
+
+/// What happened when a piece locked, every lock that clears a line or is a T-Spin is one of these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineClear {
+    pub lines: u8,
+    pub t_spin: TSpin,
+    // If this is a Tetris or a T-Spin that cleared lines, right after another one.
+    pub back_to_back: bool,
+    // How many line clears in a row came right before this one, 0 for the first.
+    pub combo: u32,
+    // If the board is completely empty afterwards.
+    pub perfect_clear: bool,
+}
+
+impl LineClear {
+    /// Tetrises and T-Spins that clear lines are "difficult", doing them in a row is a Back-to-Back.
+    pub fn is_difficult(&self) -> bool {
+        self.lines == 4 || (self.lines > 0 && self.t_spin != TSpin::None)
+    }
+
+    /// The name of the clear in all caps, like "TETRIS" or "B2B T-SPIN MINI\nSINGLE".
+    pub fn name(&self) -> String {
+        let lines = match self.lines {
+            0 => "",
+            1 => "SINGLE",
+            2 => "DOUBLE",
+            3 => "TRIPLE",
+            _ => "TETRIS",
+        };
+
+        let mut name = match self.t_spin {
+            TSpin::None => lines.to_string(),
+            TSpin::Mini => format!("T-SPIN MINI\n{}", lines),
+            TSpin::Full => format!("T-SPIN\n{}", lines),
+        };
+
+        if self.back_to_back {
+            name = format!("B2B {}", name);
+        }
+
+        if self.combo > 0 {
+            name = format!("{}\n{} COMBO", name, self.combo);
+        }
+
+        if self.perfect_clear {
+            name = format!("{}\nPERFECT CLEAR", name);
+        }
+
+        name
+    }
+}
+
+/// Keeps count of every kind of line clear, for the stats in the sidebar.
+#[derive(Clone, Debug, Default)]
+pub struct ClearStats {
+    // How many times 1, 2, 3 and 4 lines were cleared at once.
+    pub lines: [u128; 4],
+    // T-Spins of any kind, with or without lines.
+    pub t_spins: u128,
+    pub back_to_backs: u128,
+    // The longest combo of the game.
+    pub max_combo: u32,
+    pub perfect_clears: u128,
+}
+
+impl ClearStats {
+    pub fn record(&mut self, clear: &LineClear) {
+        if (1..=4).contains(&clear.lines) {
+            self.lines[clear.lines as usize - 1] += 1;
+        }
+
+        if clear.t_spin != TSpin::None {
+            self.t_spins += 1;
+        }
+
+        if clear.back_to_back {
+            self.back_to_backs += 1;
+        }
+
+        if clear.perfect_clear {
+            self.perfect_clears += 1;
+        }
+
+        self.max_combo = self.max_combo.max(clear.combo);
+    }
+}
+
+/// Checks if the piece, about to lock on the board, is a T-Spin with the 3-corner rule.
+/// A T piece whose last successful move was a rotation is a T-Spin if at least 3 of the 4 corners
+/// around its center are filled, walls and the floor count as filled.
+/// If only one of the two corners the T points at is filled it is a Mini,
+/// unless the rotation used a kick of 2 rows and 1 column, like the last SRS kick.
+pub fn t_spin(piece: &Piece, board: &Board, rotated_last: bool) -> TSpin {
+    if piece.piece_type != PieceType::T || !rotated_last {
+        return TSpin::None;
+    }
+
+    let blocks = piece.blocks();
+
+    // The center of the T is the block that touches the other three.
+    let center = match blocks
+        .iter()
+        .find(|&&b| blocks.iter().filter(|&&o| is_neighbour(b, o)).count() == 3)
+    {
+        Some(&center) => (center.0 as isize, center.1 as isize),
+        None => return TSpin::None,
+    };
+
+    // The T points to where the block is that has no block on the opposite side of the center.
+    let pointing = blocks
+        .iter()
+        .map(|&b| (b.0 as isize - center.0, b.1 as isize - center.1))
+        .filter(|&d| d != (0, 0))
+        .find(|&d| {
+            !blocks
+                .iter()
+                .any(|&b| (b.0 as isize, b.1 as isize) == (center.0 - d.0, center.1 - d.1))
+        })
+        .unwrap_or((-1, 0));
+
+    let filled = |y: isize, x: isize| y < 0 || x < 0 || board.collides(&[(y as usize, x as usize)]);
+
+    let corners = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+    let filled_corners = corners
+        .iter()
+        .filter(|&&(dy, dx)| filled(center.0 + dy, center.1 + dx))
+        .count();
+
+    if filled_corners < 3 {
+        return TSpin::None;
+    }
+
+    // The two corners on the side the T points to.
+    let front_corners = corners
+        .iter()
+        .filter(|&&(dy, dx)| {
+            (pointing.0 != 0 && dy == pointing.0) || (pointing.1 != 0 && dx == pointing.1)
+        })
+        .filter(|&&(dy, dx)| filled(center.0 + dy, center.1 + dx))
+        .count();
+
+    let big_kick = piece
+        .last_kick
+        .is_some_and(|(dy, dx)| dy.abs() == 2 && dx.abs() == 1);
+
+    if front_corners == 2 || big_kick {
+        TSpin::Full
+    } else {
+        TSpin::Mini
+    }
+}
+
+fn is_neighbour(a: (usize, usize), b: (usize, usize)) -> bool {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1) == 1
+}
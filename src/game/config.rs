@@ -1,34 +1,156 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::animation::AnimationSettings;
+use super::board::{DEFAULT_HEIGHT, DEFAULT_WIDTH};
+use super::controls::Controls;
+use super::engine::LockReset;
+use super::gravity::GravityCurve;
+use super::mode::GameMode;
+use super::randomizer::{PieceWeights, RandomizerKind};
+use super::replay::ReplayFormat;
+use super::rotation::RotationSystemKind;
+use super::scoring::{ScoringKind, ScoringTable};
+
+/// The version of the config format. Bump this and add a step to `migrate`
+/// whenever a setting is renamed or changes its meaning.
+pub const CONFIG_VERSION: u32 = 3;
+
+// The longest delay in milliseconds for `lock_delay`, `das` and `arr`, anything longer is no use in a game anyway.
+pub const MAX_DELAY_MS: u32 = 10_000;
+
+// The name of the folder in the config directory of the operating system.
+const APP_NAME: &str = "tetris-rust";
 
 // What the values do exactly is explained in README.md.
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Config {
+    #[serde(default = "version_default")]
+    pub version: u32,
+
+    #[serde(default = "mode_default")]
+    pub mode: GameMode,
+
+    #[serde(default = "board_width_default")]
+    pub board_width: usize,
+
+    #[serde(default = "board_height_default")]
+    pub board_height: usize,
+
+    #[serde(default = "partial_lock_out_default")]
+    pub partial_lock_out: bool,
+
     #[serde(default = "colored_board_default")]
     pub colored_board: bool,
 
-    #[serde(default = "modern_piece_rng_default")]
-    pub modern_piece_rng: bool,
+    #[serde(default)]
+    pub animations: AnimationSettings,
+
+    #[serde(default = "randomizer_default")]
+    pub randomizer: RandomizerKind,
 
     #[serde(default = "bag_amount_default")]
     pub bag_amount: u8,
 
+    #[serde(default)]
+    pub piece_weights: PieceWeights,
+
     #[serde(default = "first_piece_no_overhang_default")]
     pub first_piece_no_overhang: bool,
 
     #[serde(default = "holding_enabled_default")]
     pub holding_enabled: bool,
+
+    #[serde(default = "preview_count_default")]
+    pub preview_count: u8,
+
+    #[serde(default = "rotation_system_default")]
+    pub rotation_system: RotationSystemKind,
+
+    #[serde(default = "scoring_default")]
+    pub scoring: ScoringKind,
+
+    #[serde(default)]
+    pub custom_scoring: ScoringTable,
+
+    #[serde(default = "gravity_default")]
+    pub gravity: GravityCurve,
+
+    #[serde(default = "custom_gravity_default")]
+    pub custom_gravity: Vec<f64>,
+
+    #[serde(default = "lock_delay_default")]
+    pub lock_delay: u32,
+
+    #[serde(default = "lock_reset_default")]
+    pub lock_reset: LockReset,
+
+    #[serde(default = "lock_reset_limit_default")]
+    pub lock_reset_limit: u32,
+
+    #[serde(default = "line_clear_delay_default")]
+    pub line_clear_delay: u32,
+
+    #[serde(default = "are_default")]
+    pub are: u32,
+
+    #[serde(default = "irs_default")]
+    pub irs: bool,
+
+    #[serde(default = "ihs_default")]
+    pub ihs: bool,
+
+    #[serde(default = "das_default")]
+    pub das: u32,
+
+    #[serde(default = "arr_default")]
+    pub arr: u32,
+
+    #[serde(default = "soft_drop_factor_default")]
+    pub soft_drop_factor: u32,
+
+    #[serde(default)]
+    pub controls: Controls,
+
+    #[serde(default = "seed_default")]
+    pub seed: Option<u64>,
+
+    #[serde(default = "save_replays_default")]
+    pub save_replays: bool,
+
+    #[serde(default = "replay_format_default")]
+    pub replay_format: ReplayFormat,
 }
 
-fn colored_board_default() -> bool {
+fn version_default() -> u32 {
+    CONFIG_VERSION
+}
+fn mode_default() -> GameMode {
+    GameMode::Marathon
+}
+fn board_width_default() -> usize {
+    DEFAULT_WIDTH
+}
+fn board_height_default() -> usize {
+    DEFAULT_HEIGHT
+}
+fn partial_lock_out_default() -> bool {
     true
 }
-fn modern_piece_rng_default() -> bool {
+fn colored_board_default() -> bool {
     true
 }
+fn randomizer_default() -> RandomizerKind {
+    RandomizerKind::Bag
+}
 fn bag_amount_default() -> u8 {
     5
 }
@@ -38,37 +160,597 @@ fn first_piece_no_overhang_default() -> bool {
 fn holding_enabled_default() -> bool {
     true
 }
+fn preview_count_default() -> u8 {
+    5
+}
+fn rotation_system_default() -> RotationSystemKind {
+    RotationSystemKind::Srs
+}
+fn scoring_default() -> ScoringKind {
+    ScoringKind::Nes
+}
+fn gravity_default() -> GravityCurve {
+    GravityCurve::Classic
+}
+fn custom_gravity_default() -> Vec<f64> {
+    // The guideline curve up to level 15.
+    vec![
+        0.01667, 0.02102, 0.02698, 0.03526, 0.04692, 0.06361, 0.08787, 0.1237, 0.17753, 0.2598,
+        0.38781, 0.59065, 0.91811, 1.45696, 2.36118,
+    ]
+}
+fn lock_delay_default() -> u32 {
+    500
+}
+fn lock_reset_default() -> LockReset {
+    LockReset::Move
+}
+fn lock_reset_limit_default() -> u32 {
+    15
+}
+fn line_clear_delay_default() -> u32 {
+    12
+}
+fn are_default() -> u32 {
+    0
+}
+fn irs_default() -> bool {
+    true
+}
+fn ihs_default() -> bool {
+    true
+}
+fn das_default() -> u32 {
+    167
+}
+fn arr_default() -> u32 {
+    33
+}
+fn soft_drop_factor_default() -> u32 {
+    20
+}
+fn seed_default() -> Option<u64> {
+    None
+}
+fn save_replays_default() -> bool {
+    true
+}
+fn replay_format_default() -> ReplayFormat {
+    ReplayFormat::Compact
+}
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: version_default(),
+            mode: mode_default(),
+            board_width: board_width_default(),
+            board_height: board_height_default(),
+            partial_lock_out: partial_lock_out_default(),
             colored_board: colored_board_default(),
-            modern_piece_rng: modern_piece_rng_default(),
+            animations: AnimationSettings::default(),
+            randomizer: randomizer_default(),
             bag_amount: bag_amount_default(),
+            piece_weights: PieceWeights::default(),
             first_piece_no_overhang: first_piece_no_overhang_default(),
             holding_enabled: holding_enabled_default(),
+            preview_count: preview_count_default(),
+            rotation_system: rotation_system_default(),
+            scoring: scoring_default(),
+            custom_scoring: ScoringTable::default(),
+            gravity: gravity_default(),
+            custom_gravity: custom_gravity_default(),
+            lock_delay: lock_delay_default(),
+            lock_reset: lock_reset_default(),
+            lock_reset_limit: lock_reset_limit_default(),
+            line_clear_delay: line_clear_delay_default(),
+            are: are_default(),
+            irs: irs_default(),
+            ihs: ihs_default(),
+            das: das_default(),
+            arr: arr_default(),
+            soft_drop_factor: soft_drop_factor_default(),
+            controls: Controls::default(),
+            seed: seed_default(),
+            save_replays: save_replays_default(),
+            replay_format: replay_format_default(),
         }
     }
 }
 
+/// Everything that can go wrong while loading the config.
+#[derive(Debug)]
+pub enum ConfigError {
+    // The file could not be read at all.
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    // The file is not a valid JSON object, so none of it can be used.
+    Syntax {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    // A single setting has a wrong value, only this one falls back to its default.
+    Field {
+        path: PathBuf,
+        field: String,
+        line: Option<usize>,
+        message: String,
+    },
+    // A setting this version of the game does not know about, it is ignored.
+    UnknownField {
+        path: PathBuf,
+        field: String,
+        line: Option<usize>,
+    },
+    // The file was written for a newer version of the game than this one.
+    NewerVersion {
+        path: PathBuf,
+        version: u32,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "Could not read {}: {}", path.display(), source),
+            // The message of serde_json already says where in the file the error is.
+            Self::Syntax { path, message, .. } => write!(f, "{}: {}", path.display(), message),
+            Self::Field {
+                path,
+                field,
+                line,
+                message,
+            } => write!(
+                f,
+                "{}{}: {}: {}",
+                path.display(),
+                line_suffix(*line),
+                field,
+                message
+            ),
+            Self::UnknownField { path, field, line } => write!(
+                f,
+                "{}{}: There is no setting called \"{}\", it is ignored.",
+                path.display(),
+                line_suffix(*line),
+                field
+            ),
+            Self::NewerVersion { path, version } => write!(
+                f,
+                "{} is from a newer version of the game (config version {}, this game uses {}), some settings might not work.",
+                path.display(),
+                version,
+                CONFIG_VERSION
+            ),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+fn line_suffix(line: Option<usize>) -> String {
+    line.map_or_else(String::new, |l| format!(", line {}", l))
+}
+
+/// Loads the config, see `find_config` for where we look for it.
+/// Nothing in here can crash the game, every problem is printed and the affected settings use their defaults.
+/// A seed given with `--seed <number>` on the command line replaces the one in the file.
 pub fn load_config() -> Config {
-    if !Path::new("./config.json").exists() {
-        println!(
-            "Create a config.json file to configure this game. Using default settings for now..."
+    let mut config = load_config_file();
+
+    if let Some(seed) = argument(env::args().skip(1), "seed") {
+        match seed.parse() {
+            Ok(seed) => config.seed = Some(seed),
+            Err(_) => println!(
+                "The seed has to be a whole number between 0 and {}, not \"{}\". Using a random seed instead...",
+                u64::MAX,
+                seed
+            ),
+        }
+    }
+
+    config
+}
+
+fn load_config_file() -> Config {
+    let path = match find_config() {
+        Some(path) => path,
+        None => {
+            println!(
+                "Create a config.json file to configure this game. Using default settings for now..."
+            );
+
+            return Config::default();
+        }
+    };
+
+    match read_config(&path) {
+        Ok((config, problems)) => {
+            for problem in problems {
+                println!("{}", problem);
+            }
+
+            config
+        }
+        Err(error) => {
+            println!("{}", error);
+            println!("Using default settings for now...");
+
+            Config::default()
+        }
+    }
+}
+
+/// Reads the config file at the given path.
+/// If the file can be read, you get the config back together with every problem found in it,
+/// invalid settings are replaced with their defaults. Only an unreadable file or invalid JSON is an error.
+pub fn read_config(path: &Path) -> Result<(Config, Vec<ConfigError>), ConfigError> {
+    let json_file = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    parse_config(&json_file, path)
+}
+
+/// Writes the settings with these names back into the config file, the one `find_config` finds,
+/// or a new one in the config directory if there is none. Returns where the file was saved.
+/// Every other setting in the file stays as it is, and the settings keep their order.
+pub fn save_settings(config: &Config, fields: &[&str]) -> io::Result<PathBuf> {
+    let path = find_config()
+        .or_else(|| config_dir().map(|dir| dir.join("config.json")))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "There is no config folder."))?;
+
+    let mut settings = match fs::read_to_string(&path) {
+        Ok(json) => match serde_json::from_str::<Value>(&json) {
+            Ok(Value::Object(settings)) => settings,
+            // We would rather not overwrite a file you can still fix by hand.
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} is not a valid config file.", path.display()),
+                ))
+            }
+        },
+        Err(_) => {
+            let mut settings = Map::new();
+            settings.insert("version".to_string(), Value::from(CONFIG_VERSION));
+            settings
+        }
+    };
+
+    let values = serde_json::to_value(config)?;
+
+    for &field in fields {
+        if let Some(value) = values.get(field) {
+            settings.insert(field.to_string(), value.clone());
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Indented with 4 spaces, like the config file that comes with the game.
+    let mut json = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut json, formatter);
+    Value::Object(settings).serialize(&mut serializer)?;
+    json.push(b'\n');
+
+    fs::write(&path, json)?;
+
+    Ok(path)
+}
+
+/// Parses the contents of a config file, the path is only used for the error messages.
+pub fn parse_config(json: &str, path: &Path) -> Result<(Config, Vec<ConfigError>), ConfigError> {
+    let mut settings = match serde_json::from_str::<Value>(json) {
+        Ok(Value::Object(settings)) => settings,
+        Ok(_) => {
+            return Err(ConfigError::Syntax {
+                path: path.to_path_buf(),
+                line: 1,
+                column: 1,
+                message: "The config has to be a JSON object, starting with {.".to_string(),
+            })
+        }
+        Err(e) => {
+            return Err(ConfigError::Syntax {
+                path: path.to_path_buf(),
+                line: e.line(),
+                column: e.column(),
+                message: e.to_string(),
+            })
+        }
+    };
+
+    let mut problems = Vec::new();
+
+    // Files from before the version key existed are version 1.
+    let version = match settings.get("version") {
+        None => 1,
+        Some(v) => match v.as_u64() {
+            Some(v) => v as u32,
+            None => {
+                problems.push(ConfigError::Field {
+                    path: path.to_path_buf(),
+                    field: "version".to_string(),
+                    line: line_of_field(json, "version"),
+                    message: "has to be a whole number, assuming the current version.".to_string(),
+                });
+
+                CONFIG_VERSION
+            }
+        },
+    };
+
+    if version > CONFIG_VERSION {
+        problems.push(ConfigError::NewerVersion {
+            path: path.to_path_buf(),
+            version,
+        });
+    } else if version < CONFIG_VERSION {
+        migrate(&mut settings, version);
+    }
+
+    settings.remove("version");
+
+    // We start from the default config and put in the settings one by one,
+    // so a single wrong setting only resets that setting and not the whole file.
+    let mut merged = serde_json::to_value(Config::default()).unwrap();
+
+    for (field, value) in settings {
+        if merged.get(&field).is_none() {
+            problems.push(ConfigError::UnknownField {
+                path: path.to_path_buf(),
+                line: line_of_field(json, &field),
+                field,
+            });
+            continue;
+        }
+
+        let previous = merged[&field].clone();
+        merged[&field] = value;
+
+        if let Err(e) = serde_json::from_value::<Config>(merged.clone()) {
+            merged[&field] = previous;
+
+            problems.push(ConfigError::Field {
+                path: path.to_path_buf(),
+                line: line_of_field(json, &field),
+                field,
+                message: format!("{}. Using the default value instead.", e),
+            });
+        }
+    }
+
+    let mut config: Config = serde_json::from_value(merged).unwrap();
+
+    for (field, delay) in [
+        ("lock_delay", &mut config.lock_delay),
+        ("das", &mut config.das),
+        ("arr", &mut config.arr),
+    ] {
+        if *delay > MAX_DELAY_MS {
+            *delay = MAX_DELAY_MS;
+
+            problems.push(ConfigError::Field {
+                path: path.to_path_buf(),
+                field: field.to_string(),
+                line: line_of_field(json, field),
+                message: format!(
+                    "can be at most {} milliseconds, using that instead.",
+                    MAX_DELAY_MS
+                ),
+            });
+        }
+    }
+
+    let conflicts = config.controls.conflicts();
+
+    // A key can only do one thing, so if any are bound twice we rather use the preset than guess.
+    if !conflicts.is_empty() {
+        for conflict in conflicts {
+            problems.push(ConfigError::Field {
+                path: path.to_path_buf(),
+                field: "controls".to_string(),
+                line: line_of_field(json, "controls"),
+                message: format!("{} Using the keys of the preset instead.", conflict),
+            });
+        }
+
+        config.controls = Controls::from_preset(config.controls.preset);
+    }
+
+    Ok((config, problems))
+}
+
+/// Updates the settings of an older config file to the current version, one version at a time.
+fn migrate(settings: &mut Map<String, Value>, version: u32) {
+    // Version 1 is every config file from before the version key.
+    // Since then settings were only added, and they all have defaults, so nothing has to change.
+    if version < 2 {
+        settings.insert("version".to_string(), Value::from(2));
+    }
+
+    // Version 3 replaced the modern_piece_rng switch with the randomizer setting.
+    if version < 3 {
+        let modern = settings
+            .remove("modern_piece_rng")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+
+        if !settings.contains_key("randomizer") {
+            let randomizer = if modern { "bag" } else { "random" };
+            settings.insert("randomizer".to_string(), Value::from(randomizer));
+        }
+
+        settings.insert("version".to_string(), Value::from(3));
+    }
+}
+
+/// Gets the line number of a setting in the file, for the error messages.
+fn line_of_field(json: &str, field: &str) -> Option<usize> {
+    let key = format!("\"{}\"", field);
+
+    json.lines()
+        .position(|line| line.contains(&key))
+        .map(|i| i + 1)
+}
+
+/// Finds the config file. In order, we look at:
+/// 1. The path given with `--config <path>` on the command line, this one is used even if it does not exist.
+/// 2. `config.json` in the current working directory.
+/// 3. `config.json` in the config directory of the operating system,
+///    `$XDG_CONFIG_HOME/tetris-rust/` or `~/.config/tetris-rust/` on Linux and `%APPDATA%\tetris-rust\` on Windows.
+/// 4. `config.json` next to the executable.
+pub fn find_config() -> Option<PathBuf> {
+    if let Some(path) = argument(env::args().skip(1), "config") {
+        return Some(PathBuf::from(path));
+    }
+
+    let mut candidates = vec![PathBuf::from("./config.json")];
+
+    if let Some(dir) = config_dir() {
+        candidates.push(dir.join("config.json"));
+    }
+
+    if let Some(dir) = executable_dir() {
+        candidates.push(dir.join("config.json"));
+    }
+
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+/// The name of a setting like the randomizer, the same as in the config file.
+pub fn setting_name<T: Serialize>(setting: &T) -> String {
+    match serde_json::to_value(setting) {
+        Ok(Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+/// Looks for `--<name> <value>` or `--<name>=<value>` in the command line arguments.
+pub fn argument(mut args: impl Iterator<Item = String>, name: &str) -> Option<String> {
+    let flag = format!("--{}", name);
+    let prefix = format!("--{}=", name);
+
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+    }
+
+    None
+}
+
+/// The folder for this game in the config directory of the operating system.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+
+    base.map(|dir| dir.join(APP_NAME))
+}
+
+/// The folder the executable is in.
+pub fn executable_dir() -> Option<PathBuf> {
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> (Config, Vec<ConfigError>) {
+        parse_config(json, Path::new("config.json")).unwrap()
+    }
+
+    #[test]
+    fn baseline_config_migrates_to_the_randomizer_setting() {
+        let (config, problems) = parse(
+            r#"{
+    "colored_board": true,
+    "modern_piece_rng": false,
+    "bag_amount": 5,
+    "first_piece_no_overhang": true,
+    "holding_enabled": true
+}"#,
         );
 
-        return Config::default();
+        assert!(problems.is_empty());
+        assert_eq!(config.randomizer, RandomizerKind::Random);
+        assert_eq!(config.bag_amount, 5);
     }
 
-    let json_file = fs::read_to_string("./config.json").unwrap();
+    #[test]
+    fn wrong_setting_keeps_its_default_and_reports_its_line() {
+        let (config, problems) = parse(
+            r#"{
+    "version": 3,
+    "das": "fast",
+    "arr": 50
+}"#,
+        );
+
+        assert_eq!(config.das, das_default());
+        assert_eq!(config.arr, 50);
+        assert_eq!(problems.len(), 1);
+        assert!(matches!(
+            &problems[0],
+            ConfigError::Field { field, line: Some(3), .. } if field == "das"
+        ));
+    }
+
+    #[test]
+    fn unknown_settings_and_newer_versions_are_reported() {
+        let (config, problems) = parse(
+            r#"{
+    "version": 99,
+    "colored_board": false,
+    "sound": true
+}"#,
+        );
+
+        assert!(!config.colored_board);
+        assert_eq!(problems.len(), 2);
+        assert!(matches!(
+            problems[0],
+            ConfigError::NewerVersion { version: 99, .. }
+        ));
+        assert!(matches!(
+            &problems[1],
+            ConfigError::UnknownField { field, line: Some(4), .. } if field == "sound"
+        ));
+    }
 
-    let c: Config = serde_json::from_str(&json_file).unwrap();
+    #[test]
+    fn broken_json_is_a_syntax_error() {
+        let result = parse_config("{\n    \"das\": 100,\n}", Path::new("config.json"));
 
-    Config {
-        colored_board: c.colored_board,
-        modern_piece_rng: c.modern_piece_rng,
-        bag_amount: c.bag_amount,
-        first_piece_no_overhang: c.first_piece_no_overhang,
-        holding_enabled: c.holding_enabled,
+        assert!(matches!(result, Err(ConfigError::Syntax { line: 3, .. })));
     }
 }
@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+
+use super::input::Action;
+
+/// Everything that can be bound to a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Control {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateCounterClockwise,
+    Rotate180,
+    Hold,
+    Pause,
+    Restart,
+}
+
+impl Control {
+    pub const ALL: [Self; 10] = [
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::SoftDrop,
+        Self::HardDrop,
+        Self::RotateClockwise,
+        Self::RotateCounterClockwise,
+        Self::Rotate180,
+        Self::Hold,
+        Self::Pause,
+        Self::Restart,
+    ];
+
+    /// The engine action of the control, pausing and restarting are up to the frontend.
+    pub fn action(self) -> Option<Action> {
+        match self {
+            Self::MoveLeft => Some(Action::MoveLeft),
+            Self::MoveRight => Some(Action::MoveRight),
+            Self::SoftDrop => Some(Action::SoftDrop),
+            Self::HardDrop => Some(Action::HardDrop),
+            Self::RotateClockwise => Some(Action::RotateClockwise),
+            Self::RotateCounterClockwise => Some(Action::RotateCounterClockwise),
+            Self::Rotate180 => Some(Action::Rotate180),
+            Self::Hold => Some(Action::Hold),
+            Self::Pause | Self::Restart => None,
+        }
+    }
+
+    /// The name of the control in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MoveLeft => "move_left",
+            Self::MoveRight => "move_right",
+            Self::SoftDrop => "soft_drop",
+            Self::HardDrop => "hard_drop",
+            Self::RotateClockwise => "rotate_clockwise",
+            Self::RotateCounterClockwise => "rotate_counter_clockwise",
+            Self::Rotate180 => "rotate_180",
+            Self::Hold => "hold",
+            Self::Pause => "pause",
+            Self::Restart => "restart",
+        }
+    }
+}
+
+/// The set of key bindings to start from, before your own bindings are applied.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlsPreset {
+    // The controls this game always had, see README.md.
+    #[default]
+    Classic,
+    // The default controls of the modern, guideline Tetris games.
+    Guideline,
+}
+
+impl ControlsPreset {
+    /// The keys bound to the control in this preset.
+    pub fn keys(self, control: Control) -> &'static [&'static str] {
+        match self {
+            Self::Classic => match control {
+                Control::MoveLeft => &["Left"],
+                Control::MoveRight => &["Right"],
+                Control::SoftDrop => &["Down"],
+                Control::HardDrop => &["Up"],
+                Control::RotateClockwise => &["Space"],
+                Control::RotateCounterClockwise => &["LAlt"],
+                Control::Rotate180 => &["LControl"],
+                Control::Hold => &["Numpad0"],
+                Control::Pause => &["Escape"],
+                Control::Restart => &["Return"],
+            },
+            Self::Guideline => match control {
+                Control::MoveLeft => &["Left", "Numpad4"],
+                Control::MoveRight => &["Right", "Numpad6"],
+                Control::SoftDrop => &["Down", "Numpad2"],
+                Control::HardDrop => &["Space", "Numpad8"],
+                Control::RotateClockwise => &["Up", "X", "Numpad1", "Numpad5", "Numpad9"],
+                Control::RotateCounterClockwise => {
+                    &["Z", "LControl", "RControl", "Numpad3", "Numpad7"]
+                }
+                Control::Rotate180 => &["A"],
+                Control::Hold => &["C", "LShift", "RShift", "Numpad0"],
+                Control::Pause => &["Escape", "F1"],
+                Control::Restart => &["Return"],
+            },
+        }
+    }
+}
+
+/// The key bindings, every control can have as many keys as you want.
+/// The key names are the names of the ggez `KeyCode`s, like "Left", "Space", "LShift" or "Numpad0".
+/// Controls you leave out use the keys of the preset.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Controls {
+    #[serde(default)]
+    pub preset: ControlsPreset,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub move_left: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub move_right: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_drop: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hard_drop: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotate_clockwise: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotate_counter_clockwise: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotate_180: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hold: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pause: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<Vec<String>>,
+}
+
+impl Controls {
+    /// Only the keys of the preset, without any of your own bindings.
+    pub fn from_preset(preset: ControlsPreset) -> Self {
+        Self {
+            preset,
+            ..Self::default()
+        }
+    }
+
+    fn custom_keys(&self, control: Control) -> &Option<Vec<String>> {
+        match control {
+            Control::MoveLeft => &self.move_left,
+            Control::MoveRight => &self.move_right,
+            Control::SoftDrop => &self.soft_drop,
+            Control::HardDrop => &self.hard_drop,
+            Control::RotateClockwise => &self.rotate_clockwise,
+            Control::RotateCounterClockwise => &self.rotate_counter_clockwise,
+            Control::Rotate180 => &self.rotate_180,
+            Control::Hold => &self.hold,
+            Control::Pause => &self.pause,
+            Control::Restart => &self.restart,
+        }
+    }
+
+    /// Your own keys of the control, to change them. None means the keys of the preset.
+    pub fn custom_keys_mut(&mut self, control: Control) -> &mut Option<Vec<String>> {
+        match control {
+            Control::MoveLeft => &mut self.move_left,
+            Control::MoveRight => &mut self.move_right,
+            Control::SoftDrop => &mut self.soft_drop,
+            Control::HardDrop => &mut self.hard_drop,
+            Control::RotateClockwise => &mut self.rotate_clockwise,
+            Control::RotateCounterClockwise => &mut self.rotate_counter_clockwise,
+            Control::Rotate180 => &mut self.rotate_180,
+            Control::Hold => &mut self.hold,
+            Control::Pause => &mut self.pause,
+            Control::Restart => &mut self.restart,
+        }
+    }
+
+    /// The keys bound to the control, your own if you set any, otherwise the ones of the preset.
+    pub fn keys(&self, control: Control) -> Vec<String> {
+        match self.custom_keys(control) {
+            Some(keys) => keys.clone(),
+            None => self
+                .preset
+                .keys(control)
+                .iter()
+                .map(|k| k.to_string())
+                .collect(),
+        }
+    }
+
+    /// Every key with the control it is bound to.
+    pub fn bindings(&self) -> Vec<(String, Control)> {
+        Control::ALL
+            .iter()
+            .flat_map(|&control| {
+                self.keys(control)
+                    .into_iter()
+                    .map(move |key| (key, control))
+            })
+            .collect()
+    }
+
+    /// Looks for keys that are bound to more than one control.
+    /// Returns a description of every conflict found, so an empty list means the bindings are fine.
+    pub fn conflicts(&self) -> Vec<String> {
+        let bindings = self.bindings();
+        let mut conflicts = Vec::new();
+
+        for (i, (key, control)) in bindings.iter().enumerate() {
+            for (other_key, other_control) in &bindings[i + 1..] {
+                if key.eq_ignore_ascii_case(other_key) && control != other_control {
+                    conflicts.push(format!(
+                        "The key \"{}\" is bound to both {} and {}.",
+                        key,
+                        control.name(),
+                        other_control.name()
+                    ));
+                }
+            }
+        }
+
+        conflicts
+    }
+}
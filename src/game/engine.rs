@@ -0,0 +1,1097 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::board::Board;
+use super::clear::{t_spin, ClearStats, LineClear, TSpin};
+use super::config::Config;
+use super::gravity::MAX_GRAVITY;
+use super::input::{Action, InputState};
+use super::pieces::{Piece, PieceType};
+use super::randomizer::Randomizer;
+use super::rotation::RotationSystem;
+use super::scoring::ScoringRule;
+
+/// How many times per second the engine advances when driven in real time.
+pub const FRAMES_PER_SECOND: u32 = 60;
+
+/// The random number generator behind every piece of a game.
+/// ChaCha8 gives the same numbers for the same seed on every platform and every version of rand,
+/// which the standard RNG does not promise.
+pub type GameRng = ChaCha8Rng;
+
+/// The most upcoming pieces that can be shown at once.
+pub const MAX_PREVIEWS: usize = 7;
+
+/// The actual game, without any windowing or drawing attached to it.
+/// It only advances through the inputs and ticks it receives,
+/// so it can be used in tests, bots or on a server just as well as in the window.
+#[derive(Clone)]
+pub struct Engine {
+    // Only the locked blocks, the current piece is kept separately.
+    pub board: Board,
+    pub current_piece: Piece,
+    // The upcoming pieces, the first one spawns next.
+    // There is always at least one in here, even if no previews are shown.
+    pub next_pieces: VecDeque<Piece>,
+    // Decides which pieces come next.
+    pub randomizer: Box<dyn Randomizer>,
+    pub score: u128,
+    pub level: u128,
+    pub lines_cleared: u128,
+    // You start with no held piece, so this needs to be an option.
+    // This will always be populated after you hold one piece though.
+    pub held_piece: Option<Piece>,
+    // You can only swap your hold piece once per turn.
+    pub can_swap: bool,
+    // Keeps track of how many pieces have been spawned.
+    pub piece_count: Vec<u128>,
+    // Keeps track of how many lines you clear at once, and of the T-Spins, Back-to-Backs and so on.
+    pub clear_count: ClearStats,
+    // If the last line clear was a Tetris or a T-Spin, so the next one would be a Back-to-Back.
+    pub back_to_back: bool,
+    // How many locks in a row cleared lines, minus one. None if the last lock cleared nothing.
+    pub combo: Option<u32>,
+    // You get 1 score for how far you drop a piece down, this keeps track of that feature.
+    // If you hold down from a height of 16 you will get 16 points.
+    pub down_presses: u128,
+    // The rows that were cleared by the last locked piece, so a frontend can highlight them.
+    // The engine never empties this itself, whoever consumes it should.
+    pub cleared_rows: Vec<usize>,
+    // The colors of the cleared rows, in the same order, for effects like particles.
+    pub cleared_colors: Vec<Vec<(u8, u8, u8)>>,
+    // Every line clear and T-Spin since the frontend last looked, so it can show what happened.
+    // Just like the cleared rows, whoever consumes these should empty it.
+    pub line_clears: Vec<LineClear>,
+    pub game_over: bool,
+    // If a piece is falling right now, or the game waits for the next one.
+    pub phase: Phase,
+    // How the game ended, if it did.
+    pub top_out: Option<TopOut>,
+    // If the game ended because the goal of the mode was reached, like 40 lines in Sprint.
+    pub finished: bool,
+    pub config: Config,
+    // The seed of the RNG, starting a game with the same seed and config gives the same pieces.
+    pub seed: u64,
+    // How many frames the game has been running for.
+    pub frame: u64,
+    // All of the randomness of the game comes from here.
+    rng: GameRng,
+    // Decides how many points everything is worth.
+    pub scoring: Box<dyn ScoringRule>,
+    // Decides how the pieces look, spawn and rotate.
+    pub rotation_system: Arc<dyn RotationSystem>,
+    // The actions that are held down right now, for DAS, ARR and soft dropping.
+    pub input: InputState,
+    // How far the current piece has fallen towards the next row.
+    // Once this reaches 1.0 the piece moves down one row.
+    gravity_progress: f64,
+    // How many frames the current piece has been resting on the ground.
+    lock_frames: u32,
+    // How many times the lock delay was reset by moving the current piece.
+    lock_resets: u32,
+    // The lowest row the current piece has reached, getting lower gives back the move resets.
+    lowest_row: isize,
+    // Whether the last successful move of the current piece was a rotation, for T-Spins.
+    rotated_last: bool,
+}
+
+/// How many upcoming pieces are shown, at most `MAX_PREVIEWS`.
+pub fn preview_count(config: &Config) -> usize {
+    (config.preview_count as usize).min(MAX_PREVIEWS)
+}
+
+/// What resets the lock delay of a piece resting on the ground.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockReset {
+    // Only moving down a row resets the lock delay.
+    Step,
+    // Moving or rotating the piece resets the lock delay too, up to `lock_reset_limit` times.
+    Move,
+    // The piece never locks on its own, only when you hard drop it. For training.
+    Infinite,
+}
+
+/// What the engine is doing between two frames.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Phase {
+    // The current piece is falling and can be moved.
+    Active,
+    // The line clear delay, the cleared rows are empty but the rows above them did not fall yet.
+    LineClear { frames: u32, rows: Vec<usize> },
+    // The entry delay, or ARE, the time between a lock and the next piece spawning.
+    Entry { frames: u32 },
+}
+
+/// The ways the stack can reach the top and end the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopOut {
+    // The next piece has no room to spawn.
+    BlockOut,
+    // A piece locked completely inside the hidden buffer above the visible board.
+    LockOut,
+    // A piece locked partly inside the hidden buffer, and no line clear brought it back down.
+    PartialLockOut,
+}
+
+impl TopOut {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::BlockOut => "BLOCK OUT",
+            Self::LockOut => "LOCK OUT",
+            Self::PartialLockOut => "PARTIAL LOCK OUT",
+        }
+    }
+}
+
+impl Engine {
+    /// Starts a new game, with the seed from the config or a random one if there is none.
+    pub fn new(config: Config) -> Self {
+        let rotation_system = config.rotation_system.get();
+
+        Self::with_rotation_system(config, rotation_system)
+    }
+
+    /// Starts a new game using your own rotation system, instead of the one in the config.
+    pub fn with_rotation_system(config: Config, rotation_system: Arc<dyn RotationSystem>) -> Self {
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let mut rng = GameRng::seed_from_u64(seed);
+
+        let mut randomizer = config.randomizer.get(&config);
+        let scoring = config.scoring.get(&config);
+
+        let board = Board::new(config.board_width, config.board_height);
+
+        let piece = randomizer.next(&mut rng);
+
+        let next_pieces = (0..preview_count(&config).max(1))
+            .map(|_| Piece::new(randomizer.next(&mut rng), &*rotation_system, &board))
+            .collect();
+
+        let mut e = Self {
+            current_piece: Piece::new(piece, &*rotation_system, &board),
+            board,
+            next_pieces,
+            randomizer,
+            score: 0,
+            level: 1,
+            lines_cleared: 0,
+            held_piece: None,
+            can_swap: true,
+            piece_count: vec![0; 7],
+            clear_count: ClearStats::default(),
+            back_to_back: false,
+            combo: None,
+            down_presses: 0,
+            cleared_rows: Vec::new(),
+            cleared_colors: Vec::new(),
+            line_clears: Vec::new(),
+            game_over: false,
+            phase: Phase::Active,
+            top_out: None,
+            finished: false,
+            config,
+            seed,
+            frame: 0,
+            rng,
+            scoring,
+            rotation_system,
+            input: InputState::default(),
+            gravity_progress: 0.0,
+            lock_frames: 0,
+            lock_resets: 0,
+            lowest_row: isize::MIN,
+            rotated_last: false,
+        };
+
+        Piece::spawn_piece(e.current_piece.clone(), &mut e, false);
+
+        e
+    }
+
+    /// Gets a fresh piece of the given type, as the current rotation system has it.
+    pub fn new_piece(&self, piece_type: PieceType) -> Piece {
+        Piece::new(piece_type, &*self.rotation_system, &self.board)
+    }
+
+    /// Takes the first piece out of the queue and draws a new piece for the end of it.
+    fn take_next_piece(&mut self) -> Piece {
+        let new_piece = self.randomizer.next(&mut self.rng);
+        let new_piece = self.new_piece(new_piece);
+        self.next_pieces.push_back(new_piece);
+
+        self.next_pieces.pop_front().unwrap()
+    }
+
+    /// Spawns the next piece as the current piece and draws a new piece for the end of the queue.
+    pub fn spawn_next_piece(&mut self) {
+        self.current_piece = self.take_next_piece();
+        Piece::spawn_piece(self.current_piece.clone(), self, false);
+    }
+
+    /// Spawns the next piece once the last one is locked and the delays are over.
+    /// If hold was pressed during the delays and is still held, the next piece goes straight into the hold instead,
+    /// this is the Initial Hold System.
+    fn spawn_after_lock(&mut self) {
+        self.phase = Phase::Active;
+        self.can_swap = true;
+
+        if self.config.ihs && self.config.holding_enabled && self.input.is_initial(Action::Hold) {
+            self.current_piece = self.take_next_piece();
+            self.piece_count[self.current_piece.piece_type.index()] += 1;
+
+            Piece::hold_piece(self);
+        } else {
+            self.spawn_next_piece();
+        }
+
+        self.input.clear_initial();
+    }
+
+    /// The rotation state a newly spawned piece should be in, for the Initial Rotation System.
+    /// This is the state of the rotation that was pressed during the delays and is still held, if there is one.
+    pub fn initial_rotation(&self) -> Option<usize> {
+        if !self.config.irs {
+            return None;
+        }
+
+        match self.input.initial_rotation()? {
+            Action::RotateClockwise => Some(1),
+            Action::RotateCounterClockwise => Some(3),
+            Action::Rotate180 => Some(2),
+            _ => None,
+        }
+    }
+
+    /// The upcoming pieces that should be shown, as many as the config says.
+    pub fn previews(&self) -> impl Iterator<Item = &Piece> {
+        self.next_pieces.iter().take(preview_count(&self.config))
+    }
+
+    /// How many rows per frame the current piece falls on its own, on the gravity curve of the config.
+    /// Usually this is a small fraction, 1/60 is one row per second.
+    pub fn gravity(&self) -> f64 {
+        self.config
+            .gravity
+            .rows_per_frame(self.level, &self.config.custom_gravity)
+    }
+
+    /// At 20G the piece is always on the ground, right after it spawns and after every move.
+    pub fn apply_max_gravity(&mut self) {
+        if self.gravity() >= MAX_GRAVITY {
+            while self.step_gravity() {}
+        }
+    }
+
+    /// How many frames a piece can rest on the ground before it locks.
+    pub fn lock_delay_frames(&self) -> u32 {
+        ms_to_frames(self.config.lock_delay)
+    }
+
+    /// Presses an action, like pressing a key.
+    /// Moving left or right keeps moving the piece with DAS and ARR while the action is held,
+    /// and soft dropping keeps the piece falling faster until it is released.
+    /// Returns whether the press did anything, pressing a held action again or after the game is over does not.
+    pub fn press(&mut self, action: Action) -> bool {
+        if self.game_over || !self.input.press(action) {
+            return false;
+        }
+
+        // Keys pressed during the line clear and entry delays rotate or hold the next piece when it spawns.
+        if !self.is_active() {
+            self.input.press_initial(action);
+        }
+
+        match action {
+            Action::MoveLeft => {
+                self.move_left();
+            }
+            Action::MoveRight => {
+                self.move_right();
+            }
+            Action::SoftDrop => {
+                self.soft_drop();
+            }
+            Action::HardDrop => self.hard_drop(),
+            Action::RotateClockwise => {
+                self.rotate(true);
+            }
+            Action::RotateCounterClockwise => {
+                self.rotate(false);
+            }
+            Action::Rotate180 => {
+                self.rotate_180();
+            }
+            Action::Hold => self.hold(),
+        }
+
+        true
+    }
+
+    /// Releases an action again, like letting go of a key.
+    /// Returns whether the action was held.
+    pub fn release(&mut self, action: Action) -> bool {
+        if !self.input.is_held(action) {
+            return false;
+        }
+
+        self.input.release(action);
+
+        if action == Action::SoftDrop {
+            self.release_soft_drop();
+        }
+
+        true
+    }
+
+    /// The held actions, in the order they were pressed.
+    pub fn held(&self) -> &[Action] {
+        self.input.held()
+    }
+
+    /// Lets go of every held action.
+    pub fn release_all(&mut self) {
+        for action in self.input.held().to_vec() {
+            self.release(action);
+        }
+
+        self.input.release_all();
+        self.release_soft_drop();
+    }
+
+    /// Moves the piece automatically if a direction is held long enough.
+    fn auto_shift(&mut self) {
+        let shifts = self
+            .input
+            .tick(ms_to_frames(self.config.das), ms_to_frames(self.config.arr));
+
+        let direction = self.input.direction();
+
+        for _ in 0..shifts {
+            let moved = match direction {
+                Some(Action::MoveLeft) => self.move_left(),
+                Some(Action::MoveRight) => self.move_right(),
+                _ => false,
+            };
+
+            if !moved {
+                break;
+            }
+        }
+    }
+
+    /// Checks if there is a current piece that can be moved, there is none during the delays.
+    pub fn is_active(&self) -> bool {
+        !self.game_over && self.phase == Phase::Active
+    }
+
+    /// Advances the game by a single frame, applying gravity and lock delay to the current piece,
+    /// or counting down the delay before the next piece.
+    pub fn tick(&mut self) {
+        if self.game_over {
+            return;
+        }
+
+        self.frame += 1;
+
+        if self.check_goal() {
+            return;
+        }
+
+        match &mut self.phase {
+            Phase::Active => (),
+            Phase::LineClear { frames, .. } | Phase::Entry { frames } => {
+                *frames = frames.saturating_sub(1);
+
+                let done = *frames == 0;
+
+                // DAS keeps charging while the next piece is on its way, so it can move right away.
+                self.input
+                    .tick(ms_to_frames(self.config.das), ms_to_frames(self.config.arr));
+
+                if done {
+                    self.end_delay();
+                }
+
+                return;
+            }
+        }
+
+        self.auto_shift();
+
+        let soft_dropping = self.input.is_held(Action::SoftDrop);
+
+        let mut gravity = self.gravity();
+
+        if soft_dropping {
+            gravity = (gravity * f64::from(self.config.soft_drop_factor.max(1))).min(MAX_GRAVITY);
+        }
+
+        self.gravity_progress += gravity;
+
+        while self.gravity_progress >= 1.0 {
+            self.gravity_progress -= 1.0;
+
+            if !self.step_gravity() {
+                // The piece is on the ground, no reason to build up gravity for later.
+                self.gravity_progress = 0.0;
+                break;
+            }
+
+            if soft_dropping {
+                self.down_presses += 1;
+            }
+        }
+
+        if !self.current_piece.is_grounded(&self.board) {
+            return;
+        }
+
+        let resets_used_up = self.config.lock_reset == LockReset::Move
+            && self.lock_resets >= self.config.lock_reset_limit;
+
+        match self.config.lock_reset {
+            // The piece only locks with a hard drop.
+            LockReset::Infinite => (),
+            _ => {
+                self.lock_frames += 1;
+
+                if self.lock_frames > self.lock_delay_frames() || resets_used_up {
+                    self.lock_piece();
+                }
+            }
+        }
+    }
+
+    /// Moves the current piece down by one row as if gravity pulled it.
+    /// Returns whether the piece moved, it does not lock if it could not.
+    pub fn step_gravity(&mut self) -> bool {
+        let moved =
+            self.is_active() && Piece::move_piece_down(&mut self.current_piece, &self.board);
+
+        if moved {
+            self.piece_moved_down();
+        }
+
+        moved
+    }
+
+    /// Locks the current piece in place and clears the full lines.
+    /// The next piece spawns after the line clear delay and the entry delay, right away if there are none.
+    pub fn lock_piece(&mut self) {
+        if !self.is_active() {
+            return;
+        }
+
+        let t_spin = t_spin(&self.current_piece, &self.board, self.rotated_last);
+
+        let blocks = self.current_piece.blocks();
+
+        self.board.lock(&blocks, self.current_piece.color);
+
+        // With the piece locked we increase the score, level, check for full lines and so on.
+        let lines_erased = u128::from(self.erase_lines(t_spin));
+
+        self.lines_cleared += lines_erased;
+
+        self.level = ((self.lines_cleared as f64 + 1.0) / 10.0).ceil() as u128;
+
+        self.score += self.scoring.soft_drop_points(self.down_presses);
+
+        if let Some(top_out) = self.lock_top_out(&blocks) {
+            self.game_over = true;
+            self.top_out = Some(top_out);
+            return;
+        }
+
+        if self.check_goal() {
+            return;
+        }
+
+        let rows = self.cleared_rows.clone();
+
+        if rows.is_empty() || self.config.line_clear_delay == 0 {
+            self.board.collapse_rows(&rows);
+            self.start_entry();
+        } else {
+            self.phase = Phase::LineClear {
+                frames: self.config.line_clear_delay,
+                rows,
+            };
+        }
+    }
+
+    /// Ends the game if the goal of the mode is reached, and returns if it did.
+    fn check_goal(&mut self) -> bool {
+        if self.config.mode.goal_reached(self) {
+            self.game_over = true;
+            self.finished = true;
+        }
+
+        self.finished
+    }
+
+    /// Waits for the entry delay before the next piece spawns, or spawns it right away without one.
+    fn start_entry(&mut self) {
+        if self.config.are == 0 {
+            self.spawn_after_lock();
+        } else {
+            self.phase = Phase::Entry {
+                frames: self.config.are,
+            };
+        }
+    }
+
+    /// Moves on once the current delay ran out.
+    /// After the line clear delay the rows above the cleared ones fall down, after the entry delay the next piece spawns.
+    fn end_delay(&mut self) {
+        match std::mem::replace(&mut self.phase, Phase::Active) {
+            Phase::LineClear { rows, .. } => {
+                self.board.collapse_rows(&rows);
+                self.start_entry();
+            }
+            Phase::Entry { .. } => self.spawn_after_lock(),
+            Phase::Active => (),
+        }
+    }
+
+    /// Checks if the piece that just locked with these blocks ended the game.
+    /// A piece completely above the visible board is always a lock out,
+    /// anything left in the hidden buffer after the line clears is a partial lock out, if that is turned on.
+    fn lock_top_out(&self, blocks: &[(usize, usize)]) -> Option<TopOut> {
+        if blocks.iter().all(|&(y, _)| self.board.is_hidden(y)) {
+            return Some(TopOut::LockOut);
+        }
+
+        // The cleared rows are only emptied yet, every row falls by the amount of cleared rows below it.
+        let buffer_filled = (0..self.board.buffer).any(|y| {
+            let falls = self.cleared_rows.iter().filter(|&&r| r > y).count();
+
+            !self.board.row_is_empty(y) && self.board.is_hidden(y + falls)
+        });
+
+        if self.config.partial_lock_out && buffer_filled {
+            return Some(TopOut::PartialLockOut);
+        }
+
+        None
+    }
+
+    /// Starts the lock delay over for a newly spawned piece, and forgets how the last piece moved.
+    pub fn reset_lock_delay(&mut self) {
+        self.rotated_last = false;
+        self.lock_frames = 0;
+        self.lock_resets = 0;
+        self.lowest_row = isize::MIN;
+        self.gravity_progress = 0.0;
+    }
+
+    /// Moving down a row always resets the lock delay,
+    /// and reaching a new lowest row gives the piece all of its move resets back.
+    fn piece_moved_down(&mut self) {
+        self.lock_frames = 0;
+        self.rotated_last = false;
+
+        if self.current_piece.offset.0 > self.lowest_row {
+            self.lowest_row = self.current_piece.offset.0;
+            self.lock_resets = 0;
+        }
+    }
+
+    /// Moving or rotating a piece that is about to lock resets the lock delay with move reset,
+    /// but only so many times.
+    fn piece_moved(&mut self, moved: bool) -> bool {
+        if moved
+            && self.config.lock_reset == LockReset::Move
+            && self.lock_frames > 0
+            && self.lock_resets < self.config.lock_reset_limit
+        {
+            self.lock_frames = 0;
+            self.lock_resets += 1;
+        }
+
+        if moved {
+            self.apply_max_gravity();
+        }
+
+        moved
+    }
+
+    /// Moves the current piece left, returns whether it moved.
+    pub fn move_left(&mut self) -> bool {
+        let moved =
+            self.is_active() && Piece::move_piece_left(&mut self.current_piece, &self.board);
+
+        self.piece_shifted(moved)
+    }
+
+    /// Moves the current piece right, returns whether it moved.
+    pub fn move_right(&mut self) -> bool {
+        let moved =
+            self.is_active() && Piece::move_piece_right(&mut self.current_piece, &self.board);
+
+        self.piece_shifted(moved)
+    }
+
+    fn piece_shifted(&mut self, moved: bool) -> bool {
+        if moved {
+            self.rotated_last = false;
+        }
+
+        self.piece_moved(moved)
+    }
+
+    fn piece_rotated(&mut self, rotated: bool) -> bool {
+        if rotated {
+            self.rotated_last = true;
+        }
+
+        self.piece_moved(rotated)
+    }
+
+    /// Rotates the current piece, returns whether it rotated.
+    pub fn rotate(&mut self, clockwise: bool) -> bool {
+        let moved = self.is_active()
+            && Piece::rotate_piece(
+                &mut self.current_piece,
+                &self.board,
+                &*self.rotation_system,
+                clockwise,
+            );
+
+        self.piece_rotated(moved)
+    }
+
+    /// Rotates the current piece by 180°, returns whether it rotated.
+    pub fn rotate_180(&mut self) -> bool {
+        let moved = self.is_active()
+            && Piece::rotate_piece_180(
+                &mut self.current_piece,
+                &self.board,
+                &*self.rotation_system,
+            );
+
+        self.piece_rotated(moved)
+    }
+
+    /// Moves the current piece down by one row, returns whether the piece moved.
+    /// Without a lock delay, the piece locks if it cannot go further.
+    pub fn soft_drop(&mut self) -> bool {
+        if !self.is_active() {
+            return false;
+        }
+
+        self.down_presses += 1;
+
+        let moved = self.step_gravity();
+
+        if !moved && self.lock_delay_frames() == 0 && self.config.lock_reset != LockReset::Infinite
+        {
+            self.lock_piece();
+        }
+
+        moved
+    }
+
+    /// Has to be called when the player stops soft dropping,
+    /// since the soft drop points only count for one continuous press.
+    pub fn release_soft_drop(&mut self) {
+        self.down_presses = 0;
+    }
+
+    /// Drops the current piece as far as it will go and locks it.
+    pub fn hard_drop(&mut self) {
+        if !self.is_active() {
+            return;
+        }
+
+        // Falling any distance means the rotation was not the last move anymore.
+        if !self.current_piece.is_grounded(&self.board) {
+            self.rotated_last = false;
+        }
+
+        Piece::drop_piece_down(self);
+    }
+
+    /// Swaps the current piece with the held piece.
+    pub fn hold(&mut self) {
+        if !self.is_active() {
+            return;
+        }
+
+        Piece::hold_piece(self);
+    }
+
+    /// The cells the current piece would occupy if it were hard dropped right now.
+    /// Empty during the delays, when there is no current piece.
+    pub fn shadow_piece(&self) -> Vec<(usize, usize)> {
+        if !self.is_active() {
+            return Vec::new();
+        }
+
+        Piece::get_shadow_piece(self)
+    }
+
+    /// The cells the current piece occupies on the board, empty during the delays.
+    pub fn current_piece_cells(&self) -> Vec<(usize, usize)> {
+        if !self.is_active() {
+            return Vec::new();
+        }
+
+        self.current_piece.blocks()
+    }
+
+    /// The locked board with the current piece placed on top of it, this is what the player sees.
+    pub fn composite_board(&self) -> Board {
+        let mut board = self.board.clone();
+
+        if self.is_active() {
+            board.lock(&self.current_piece.blocks(), self.current_piece.color);
+        }
+
+        board
+    }
+
+    /// Empties "full" lines on the game board and scores them, together with the T-Spin of the locked piece.
+    /// The rows above only fall down once the line clear delay is over.
+    /// Returns the amount of lines deleted.
+    pub fn erase_lines(&mut self, t_spin: TSpin) -> u8 {
+        self.cleared_rows = self.board.full_rows();
+        self.cleared_colors = self
+            .cleared_rows
+            .iter()
+            .map(|&y| self.board.row_colors(y).to_vec())
+            .collect();
+        self.board.empty_rows(&self.cleared_rows);
+
+        let erase_count = self.cleared_rows.len() as u8;
+
+        // Locking a piece without clearing anything ends the combo,
+        // but only a line clear that is not a Tetris or T-Spin ends the Back-to-Back.
+        if erase_count == 0 {
+            self.combo = None;
+            self.scoring.no_clear();
+
+            if t_spin == TSpin::None {
+                return 0;
+            }
+        } else {
+            self.combo = Some(self.combo.map_or(0, |c| c + 1));
+        }
+
+        let mut clear = LineClear {
+            lines: erase_count,
+            t_spin,
+            back_to_back: false,
+            combo: self.combo.unwrap_or(0),
+            perfect_clear: erase_count > 0 && self.board.is_empty(),
+        };
+
+        if erase_count > 0 {
+            clear.back_to_back = clear.is_difficult() && self.back_to_back;
+            self.back_to_back = clear.is_difficult();
+        }
+
+        self.score += self
+            .scoring
+            .clear_points(&clear, self.level, self.down_presses);
+        self.clear_count.record(&clear);
+        self.line_clears.push(clear);
+
+        erase_count
+    }
+}
+
+/// Converts a duration in milliseconds from the config to frames, rounding to the nearest frame.
+pub fn ms_to_frames(ms: u32) -> u32 {
+    let frames = (u64::from(ms) * u64::from(FRAMES_PER_SECOND) + 500) / 1000;
+
+    u32::try_from(frames).unwrap_or(u32::MAX)
+}
+
+/// The frames as minutes and seconds, like "2:05".
+pub fn frames_to_time(frames: u64) -> String {
+    let seconds = frames / u64::from(FRAMES_PER_SECOND);
+
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::scoring::ScoringKind;
+
+    fn engine() -> Engine {
+        Engine::new(Config {
+            seed: Some(1),
+            ..Config::default()
+        })
+    }
+
+    #[test]
+    fn partial_lock_out_with_blocks_left_in_the_buffer() {
+        let mut engine = engine();
+        let top = engine.board.buffer;
+        let blocks = [(top - 1, 4), (top, 4), (top + 1, 4), (top + 2, 4)];
+
+        engine.board.lock(&blocks, (0, 255, 255));
+
+        assert_eq!(engine.lock_top_out(&blocks), Some(TopOut::PartialLockOut));
+    }
+
+    #[test]
+    fn no_partial_lock_out_if_the_buffer_drops_into_view() {
+        let mut engine = engine();
+        let top = engine.board.buffer;
+        let blocks = [(top - 1, 4), (top, 4), (top + 1, 4), (top + 2, 4)];
+
+        engine.board.lock(&blocks, (0, 255, 255));
+        // The bottom row is cleared, so the block in the buffer falls into the visible board.
+        let bottom = engine.board.total_height() - 1;
+        engine.board.empty_rows(&[bottom]);
+        engine.cleared_rows = vec![bottom];
+
+        assert_eq!(engine.lock_top_out(&blocks), None);
+    }
+
+    #[test]
+    fn long_delays_do_not_overflow() {
+        assert_eq!(ms_to_frames(167), 10);
+        assert_eq!(ms_to_frames(u32::MAX), 257_698_038);
+    }
+
+    #[test]
+    fn frames_as_minutes_and_seconds() {
+        assert_eq!(frames_to_time(0), "0:00");
+        assert_eq!(frames_to_time(125 * 60 + 59), "2:05");
+        assert_eq!(frames_to_time(61 * 60 * 60), "61:00");
+    }
+
+    #[test]
+    fn moving_resets_the_lock_delay_up_to_the_limit() {
+        let mut engine = Engine::new(Config {
+            seed: Some(1),
+            lock_reset: LockReset::Move,
+            lock_reset_limit: 3,
+            ..Config::default()
+        });
+
+        while engine.step_gravity() {}
+        engine.tick();
+
+        assert_eq!(engine.lock_frames, 1);
+
+        for resets in 1..=3 {
+            let moved = if resets % 2 == 1 {
+                engine.move_left()
+            } else {
+                engine.move_right()
+            };
+
+            assert!(moved);
+            assert_eq!(engine.lock_frames, 0);
+            assert_eq!(engine.lock_resets, resets);
+            assert!(engine.board.is_empty());
+
+            engine.tick();
+        }
+
+        // With the resets used up, the piece locks on the ground without waiting for the delay.
+        assert!(!engine.board.is_empty());
+    }
+
+    #[test]
+    fn step_reset_only_resets_the_lock_delay_moving_down() {
+        let mut engine = Engine::new(Config {
+            seed: Some(1),
+            lock_reset: LockReset::Step,
+            ..Config::default()
+        });
+
+        while engine.step_gravity() {}
+        engine.tick();
+        engine.tick();
+
+        assert!(engine.move_left());
+        assert_eq!(engine.lock_frames, 2);
+        assert_eq!(engine.lock_resets, 0);
+
+        for _ in 0..engine.lock_delay_frames() {
+            engine.tick();
+        }
+
+        assert!(!engine.board.is_empty());
+    }
+
+    #[test]
+    fn block_out_when_the_next_piece_has_no_room() {
+        let mut engine = engine();
+        let top = engine.board.buffer;
+
+        for y in top - 2..top {
+            engine
+                .board
+                .lock(&[(y, 3), (y, 4), (y, 5), (y, 6)], (0, 0, 0));
+        }
+
+        engine.spawn_next_piece();
+
+        assert!(engine.game_over);
+        assert_eq!(engine.top_out, Some(TopOut::BlockOut));
+    }
+
+    #[test]
+    fn lock_out_when_a_piece_locks_above_the_board() {
+        let mut engine = engine();
+
+        // The whole visible board is filled, but with a hole in every row so nothing clears.
+        for y in engine.board.visible_rows() {
+            for x in 1..engine.board.width {
+                engine.board.fill(y, x, (0, 0, 0));
+            }
+        }
+
+        engine.spawn_next_piece();
+        engine.hard_drop();
+
+        assert!(engine.game_over);
+        assert_eq!(engine.top_out, Some(TopOut::LockOut));
+    }
+
+    /// An engine with a T piece as the current piece, in the given rotation and position.
+    fn t_piece_engine(rotations: usize, offset: (isize, isize)) -> Engine {
+        let mut engine = engine();
+
+        engine.current_piece = engine.new_piece(PieceType::T);
+        engine.current_piece.rotations = rotations;
+        engine.current_piece.offset = offset;
+
+        engine
+    }
+
+    fn fill(engine: &mut Engine, y: usize, columns: impl Iterator<Item = usize>) {
+        for x in columns {
+            engine.board.fill(y, x, (0, 0, 0));
+        }
+    }
+
+    #[test]
+    fn t_spin_double() {
+        let bottom = Board::default().total_height() - 1;
+        let mut engine = t_piece_engine(1, (bottom as isize - 2, 3));
+
+        fill(&mut engine, bottom, (0..10).filter(|&x| x != 4));
+        fill(
+            &mut engine,
+            bottom - 1,
+            (0..10).filter(|&x| !(3..=5).contains(&x)),
+        );
+        // The overhang over the slot.
+        fill(&mut engine, bottom - 2, 3..4);
+
+        assert!(engine.rotate(true));
+        engine.hard_drop();
+
+        let clear = engine.line_clears.last().unwrap();
+        assert_eq!(clear.t_spin, TSpin::Full);
+        assert_eq!(clear.lines, 2);
+    }
+
+    /// A T piece that ends up standing against the left wall, pointing right.
+    /// Both wall corners and the lower front corner are filled, the upper front corner is not.
+    fn left_wall_engine(rotations: usize, offset: (isize, isize)) -> Engine {
+        let bottom = Board::default().total_height() - 1;
+        let mut engine = t_piece_engine(rotations, offset);
+
+        fill(&mut engine, bottom, 1..10);
+
+        engine
+    }
+
+    #[test]
+    fn t_spin_mini() {
+        let bottom = Board::default().total_height() as isize - 1;
+        let mut engine = left_wall_engine(0, (bottom - 2, 0));
+
+        // The spot right of the wall is blocked, so the rotation kicks the piece into it.
+        assert!(engine.rotate(true));
+        assert_eq!(engine.current_piece.offset, (bottom - 2, -1));
+        engine.hard_drop();
+
+        let clear = engine.line_clears.last().unwrap();
+        assert_eq!(clear.t_spin, TSpin::Mini);
+        assert_eq!(clear.lines, 1);
+    }
+
+    #[test]
+    fn no_t_spin_if_the_piece_moved_after_rotating() {
+        let bottom = Board::default().total_height() as isize - 1;
+        let mut engine = left_wall_engine(0, (bottom - 10, 0));
+
+        // The same spot as the mini, but the piece is rotated up high and then dropped into it.
+        assert!(engine.rotate(true));
+        assert!(engine.move_left());
+        engine.hard_drop();
+
+        let clear = engine.line_clears.last().unwrap();
+        assert_eq!(clear.t_spin, TSpin::None);
+        assert_eq!(clear.lines, 1);
+    }
+
+    #[test]
+    fn hard_drops_score_the_rows_the_piece_fell() {
+        let mut engine = Engine::new(Config {
+            seed: Some(1),
+            scoring: ScoringKind::Guideline,
+            ..Config::default()
+        });
+        let start = engine.current_piece.offset.0;
+
+        engine.hard_drop();
+
+        let rows = (engine.board.total_height() as isize - 2 - start) as u128;
+        assert_eq!(engine.score, rows * 2);
+    }
+
+    #[test]
+    fn rotations_held_from_the_last_piece_do_not_rotate_the_next_one() {
+        let mut engine = engine();
+
+        engine.press(Action::RotateClockwise);
+        engine.press(Action::Hold);
+        let held = engine.held_piece.as_ref().map(|p| p.piece_type);
+        engine.press(Action::HardDrop);
+
+        assert!(engine.is_active());
+        assert_eq!(engine.current_piece.rotations, 0);
+        assert_eq!(engine.held_piece.as_ref().map(|p| p.piece_type), held);
+    }
+
+    #[test]
+    fn keys_pressed_during_the_entry_delay_rotate_and_hold_the_next_piece() {
+        let mut engine = Engine::new(Config {
+            seed: Some(1),
+            are: 30,
+            ..Config::default()
+        });
+
+        engine.press(Action::HardDrop);
+        assert!(!engine.is_active());
+
+        let next = engine.next_pieces[0].piece_type;
+        engine.press(Action::RotateClockwise);
+        engine.press(Action::Hold);
+
+        for _ in 0..30 {
+            engine.tick();
+        }
+
+        assert!(engine.is_active());
+        assert_eq!(engine.held_piece.as_ref().map(|p| p.piece_type), Some(next));
+        assert_eq!(engine.current_piece.rotations, 1);
+    }
+}
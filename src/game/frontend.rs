@@ -0,0 +1,1117 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::{self, Path};
+
+use ggez::{
+    event,
+    graphics::{self, Color, Text, TextFragment},
+    timer, GameError, GameResult,
+};
+use glam::Vec2;
+use thousands::Separable;
+
+use super::animation::{Animations, Effect};
+use super::board::Board;
+use super::clear::LineClear;
+use super::controls::{Control, Controls};
+use super::engine::{frames_to_time, Engine, FRAMES_PER_SECOND};
+use super::highscores::{ruleset, HighScores, MAX_NAME_LENGTH};
+use super::keys::key_from_name;
+use super::menu::{draw_high_score_table, Menu, MenuAction, Screen, HIGHLIGHT_COLOR};
+use super::mode::{GameMode, SPRINT_LINES, ULTRA_FRAMES};
+use super::replay::{replay_path, Playback, Recorder, Replay};
+use super::{
+    config::{argument, executable_dir, load_config},
+    pieces::PieceType,
+};
+
+/// The window side of the game, it only draws the engine and feeds it the inputs.
+pub struct MainGame {
+    pub engine: Engine,
+    pub paused: bool,
+    // Which control every bound key triggers.
+    pub bindings: HashMap<event::KeyCode, Control>,
+    // The last line clear or T-Spin, and for how many more frames it is shown.
+    pub last_clear: Option<(LineClear, u32)>,
+    // The effects that are playing right now, like flashing rows and particles.
+    pub animations: Animations,
+    // The replay that is played back instead of a game, if the game was started with `--replay`.
+    pub playback: Option<Playback>,
+    // The inputs of the game that is played right now, for its replay.
+    pub recorder: Recorder,
+    // How fast the replay is played back, 1.0 is the normal speed.
+    pub playback_speed: f64,
+    // How far the replay is towards its next frame, for slow motion.
+    playback_progress: f64,
+    // If the end of the current game was taken care of already, the replay saved and the high score checked.
+    game_over_handled: bool,
+    // Every high score table, saved to a file whenever a new score is entered.
+    pub high_scores: HighScores,
+    // The name that is typed in right now, if the game made it into the high scores.
+    pub name_entry: Option<String>,
+    // The place of the game in its high score table, so it can be highlighted.
+    pub high_score_place: Option<usize>,
+    // The menus, and which screen is shown right now.
+    pub menu: Menu,
+    // The mode that was picked in the mode select menu, restarting plays it again.
+    pub mode: GameMode,
+}
+
+// How far the arrow keys jump back and forth in a replay, in frames.
+const SEEK_FRAMES: u64 = 5 * FRAMES_PER_SECOND as u64;
+// The slowest and fastest replay speeds, the up and down keys double or halve the speed.
+const MIN_PLAYBACK_SPEED: f64 = 0.125;
+const MAX_PLAYBACK_SPEED: f64 = 8.0;
+
+// How long the name of a line clear stays on screen, in frames.
+const CLEAR_TEXT_FRAMES: u32 = 2 * FRAMES_PER_SECOND;
+
+// The board cells shrink below this size on tall or wide boards, so the board fits the window.
+const MAX_CELL_SIZE: f32 = 50.0;
+const WINDOW_HEIGHT: f32 = 1000.0;
+const MAX_BOARD_WIDTH: f32 = 750.0;
+
+impl MainGame {
+    fn new() -> GameResult<Self> {
+        let config = load_config();
+        let bindings = key_bindings(&config.controls);
+        let mode = config.mode;
+
+        let replay = argument(env::args().skip(1), "replay").and_then(|path| {
+            Replay::load(Path::new(&path))
+                .map_err(|error| println!("{}\nStarting a normal game instead...", error))
+                .ok()
+        });
+
+        // Replays are played right away, every other game starts in the title menu.
+        let screen = match replay {
+            Some(_) => Screen::Playing,
+            None => Screen::Title,
+        };
+
+        let menu = Menu::new(config.clone(), screen);
+
+        // A replay brings its own settings, only the key bindings are yours.
+        let (engine, playback) = match replay {
+            Some(replay) => (replay.engine(), Some(Playback::new(replay))),
+            None => (Engine::new(config), None),
+        };
+
+        Ok(Self {
+            animations: Animations::new(engine.config.animations.clone()),
+            engine,
+            paused: false,
+            bindings,
+            last_clear: None,
+            playback,
+            recorder: Recorder::new(),
+            playback_speed: 1.0,
+            playback_progress: 0.0,
+            game_over_handled: false,
+            high_scores: HighScores::load(),
+            name_entry: None,
+            high_score_place: None,
+            menu,
+            mode,
+        })
+    }
+
+    /// Everything besides the engine that moves on with every frame.
+    fn advance_frame(&mut self) {
+        self.animations.tick();
+        self.animations.watch(&mut self.engine);
+
+        if let Some((_, frames)) = &mut self.last_clear {
+            *frames = frames.saturating_sub(1);
+        }
+    }
+
+    /// Saves the replay of the game that just ended, and asks for your name if it made it into the high scores.
+    /// Replays that are played back do neither.
+    fn end_game(&mut self) {
+        self.game_over_handled = true;
+
+        if self.playback.is_some() {
+            return;
+        }
+
+        self.save_replay();
+
+        if self.high_scores.qualifies(&self.engine) {
+            self.name_entry = Some(self.high_scores.last_name.clone());
+        }
+    }
+
+    /// Puts the game into its high score table with the entered name, and saves the tables.
+    fn submit_high_score(&mut self) {
+        let name = match self.name_entry.take() {
+            Some(name) => name,
+            None => return,
+        };
+
+        let name = match name.trim() {
+            "" => "PLAYER",
+            name => name,
+        };
+
+        self.high_score_place = self.high_scores.insert(&self.engine, name);
+
+        if let Err(error) = self.high_scores.save() {
+            println!("Could not save the high scores: {}", error);
+        }
+    }
+
+    /// Saves the replay of the game that just ended, if the config says so.
+    fn save_replay(&mut self) {
+        if !self.engine.config.save_replays || self.playback.is_some() {
+            return;
+        }
+
+        let format = self.engine.config.replay_format;
+
+        let path = match replay_path(self.engine.seed, format) {
+            Some(path) => path,
+            None => {
+                println!("There is no folder to save the replay of this game in.");
+                return;
+            }
+        };
+
+        match self.recorder.replay(&self.engine).save(&path, format) {
+            Ok(()) => println!("Saved the replay of this game to {}", path.display()),
+            Err(error) => println!("Could not save the replay of this game: {}", error),
+        }
+    }
+
+    /// Jumps to the frame of the replay that is played back.
+    fn seek(&mut self, frame: u64) {
+        if let Some(playback) = &mut self.playback {
+            playback.seek(&mut self.engine, frame);
+        }
+
+        // Skipped frames should not leave effects behind.
+        self.animations.clear();
+        self.engine.cleared_rows.clear();
+        self.engine.line_clears.clear();
+        self.last_clear = None;
+        self.playback_progress = 0.0;
+    }
+
+    /// The keys of the replay player: pause, jumping back and forth with left and right,
+    /// and changing the speed with up and down. Restart starts the replay over.
+    fn playback_key_down(&mut self, keycode: event::KeyCode) {
+        let control = self.bindings.get(&keycode).copied();
+        let frame = self.engine.frame;
+
+        match keycode {
+            event::KeyCode::Left => self.seek(frame.saturating_sub(SEEK_FRAMES)),
+            event::KeyCode::Right => self.seek(frame + SEEK_FRAMES),
+            event::KeyCode::Up => {
+                self.playback_speed = (self.playback_speed * 2.0).min(MAX_PLAYBACK_SPEED);
+            }
+            event::KeyCode::Down => {
+                self.playback_speed = (self.playback_speed / 2.0).max(MIN_PLAYBACK_SPEED);
+            }
+            _ if control == Some(Control::Pause) => self.paused = !self.paused,
+            _ if control == Some(Control::Restart) => self.seek(0),
+            _ => (),
+        }
+    }
+
+    /// Draws the effects that play on top of the board, the flashing rows and the particles.
+    /// The falling rows and the screen shake are part of drawing the board itself.
+    fn draw_animations(&self, ctx: &mut ggez::Context, shake: Vec2) -> GameResult {
+        let cell = cell_size(&self.engine.board);
+        let buffer = self.engine.board.buffer;
+
+        for animation in &self.animations.active {
+            match &animation.effect {
+                Effect::RowFlash { rows } => {
+                    let erase_count = rows.len() as f32;
+
+                    let red_line = graphics::Mesh::new_rectangle(
+                        ctx,
+                        graphics::DrawMode::fill(),
+                        graphics::Rect::new(0.0, 0.0, board_width(&self.engine.board), cell),
+                        Color::new(
+                            // Basically the more lines erased, the more red the color will be.
+                            // A tetris being full red.
+                            1.0,
+                            0.5 - (erase_count / 8.0),
+                            0.5 - (erase_count / 8.0),
+                            animation.remaining(),
+                        ),
+                    )?;
+
+                    // Rows cleared inside the hidden buffer are not on screen.
+                    for &y in rows.iter().filter(|&&y| y >= buffer) {
+                        graphics::draw(
+                            ctx,
+                            &red_line,
+                            graphics::DrawParam::default()
+                                .dest(shake + Vec2::new(0.0, (y - buffer) as f32 * cell)),
+                        )?;
+                    }
+                }
+                Effect::Particles { particles } => {
+                    let size = (cell / 4.0).max(2.0);
+
+                    let particle_square = graphics::Mesh::new_rectangle(
+                        ctx,
+                        graphics::DrawMode::fill(),
+                        graphics::Rect::new(0.0, 0.0, size, size),
+                        Color::WHITE,
+                    )?;
+
+                    for particle in particles {
+                        let (r, g, b) = if self.engine.config.colored_board {
+                            particle.color
+                        } else {
+                            (255, 255, 255)
+                        };
+
+                        graphics::draw(
+                            ctx,
+                            &particle_square,
+                            graphics::DrawParam::default()
+                                .dest(shake + Vec2::new(particle.x * cell, particle.y * cell))
+                                .color(Color::from_rgba(
+                                    r,
+                                    g,
+                                    b,
+                                    (animation.remaining() * 255.0) as u8,
+                                )),
+                        )?;
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draws the high score table of the ruleset of the game on the game over screen,
+    /// and the name you are typing in, if you made it into the table.
+    fn draw_high_scores(&self, ctx: &mut ggez::Context, font: graphics::Font) -> GameResult {
+        let ruleset = ruleset(&self.engine.config);
+        let table = self.high_scores.table(&ruleset);
+
+        let mut title_text = Text::new(TextFragment::new("HIGH SCORES:\n").font(font).scale(21.0));
+        title_text.add(
+            TextFragment::new(ruleset.to_uppercase())
+                .font(font)
+                .scale(10.0),
+        );
+
+        graphics::draw(
+            ctx,
+            &title_text,
+            graphics::DrawParam::default().dest([15.0, 450.0]),
+        )?;
+
+        if let Some(name) = &self.name_entry {
+            let entry_text = Text::new(
+                TextFragment::new(format!("NEW HIGH SCORE! YOUR NAME:\n{}_", name))
+                    .font(font)
+                    .scale(14.0)
+                    .color(HIGHLIGHT_COLOR),
+            );
+
+            graphics::draw(
+                ctx,
+                &entry_text,
+                graphics::DrawParam::default().dest([15.0, 495.0]),
+            )?;
+        }
+
+        if table.is_empty() {
+            let empty_text = Text::new(
+                TextFragment::new("NO HIGH SCORES YET.")
+                    .font(font)
+                    .scale(14.0),
+            );
+
+            graphics::draw(
+                ctx,
+                &empty_text,
+                graphics::DrawParam::default().dest([15.0, 545.0]),
+            )?;
+        }
+
+        draw_high_score_table(
+            ctx,
+            font,
+            table,
+            self.engine.config.mode,
+            self.high_score_place,
+            [15.0, 545.0],
+        )?;
+
+        Ok(())
+    }
+
+    /// Starts a new game of the mode.
+    pub fn start_game(&mut self, mode: GameMode) {
+        self.mode = mode;
+        self.menu.open(Screen::Playing);
+        self.reset_game();
+    }
+
+    /// Leaves the game for the title menu, a game that is not over yet is thrown away.
+    pub fn quit_to_menu(&mut self) {
+        self.paused = false;
+        self.name_entry = None;
+        self.recorder.release_all(&mut self.engine);
+        self.menu.open(Screen::Title);
+    }
+
+    /// Resets the game.
+    pub fn reset_game(&mut self) {
+        let mut config = load_config();
+        config.mode = self.mode;
+
+        self.bindings = key_bindings(&config.controls);
+        self.animations = Animations::new(config.animations.clone());
+        self.engine = Engine::new(config);
+        self.recorder = Recorder::new();
+        self.paused = false;
+        self.last_clear = None;
+        self.game_over_handled = false;
+        self.name_entry = None;
+        self.high_score_place = None;
+    }
+}
+
+impl event::EventHandler<GameError> for MainGame {
+    fn update(&mut self, ctx: &mut ggez::Context) -> Result<(), GameError> {
+        while timer::check_update_time(ctx, FRAMES_PER_SECOND) {
+            if self.paused || self.menu.screen != Screen::Playing {
+                continue;
+            }
+
+            match self.playback.take() {
+                // Replays can be slower or faster than the game, so they can take any amount of frames at once.
+                Some(mut playback) => {
+                    self.playback_progress += self.playback_speed;
+
+                    while self.playback_progress >= 1.0 {
+                        self.playback_progress -= 1.0;
+
+                        playback.step(&mut self.engine);
+                        self.advance_frame();
+                    }
+
+                    self.playback = Some(playback);
+                }
+                None => {
+                    self.engine.tick();
+                    self.advance_frame();
+                }
+            }
+        }
+
+        if self.engine.game_over && !self.game_over_handled {
+            self.end_game();
+        }
+
+        if let Some(clear) = self.engine.line_clears.drain(..).next_back() {
+            self.last_clear = Some((clear, CLEAR_TEXT_FRAMES));
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut ggez::Context) -> Result<(), GameError> {
+        graphics::clear(ctx, Color::BLACK);
+
+        if self.menu.screen != Screen::Playing {
+            let font = graphics::Font::new(ctx, "/fonts/PressStart2P-Regular.ttf")?;
+
+            self.menu.draw(ctx, font, &self.high_scores)?;
+
+            graphics::present(ctx)?;
+            graphics::clear_font_cache(ctx);
+
+            return Ok(());
+        }
+
+        let cell = cell_size(&self.engine.board);
+        // Where the sidebar starts, right of the board.
+        let left = board_width(&self.engine.board);
+
+        let square = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, cell - 1.0, cell - 1.0),
+            Color::WHITE,
+        )?;
+
+        let held_square = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, 49.0, 49.0),
+            Color::WHITE,
+        )?;
+
+        let shadow_square_outline = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(1.0),
+            graphics::Rect::new(0.0, 0.0, cell - 1.0, cell - 1.0),
+            self.engine.current_piece.color.into(),
+        )?;
+
+        let shadow_square_fill = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, cell - 1.0, cell - 1.0),
+            Color::new(
+                f32::from(self.engine.current_piece.color.0),
+                f32::from(self.engine.current_piece.color.1),
+                f32::from(self.engine.current_piece.color.2),
+                0.1,
+            ),
+        )?;
+
+        let mini_square = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, 11.0, 11.0),
+            Color::WHITE,
+        )?;
+
+        let piece_windows = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, 280.0, 180.0),
+            Color::BLACK,
+        )?;
+
+        let count_window = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(left + 10.0, 450.0, 280.0, 340.0),
+            Color::BLACK,
+        )?;
+
+        let preview_count = self.engine.previews().count();
+
+        let preview_window = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, 130.0, (preview_count as f32).mul_add(100.0, 40.0)),
+            Color::BLACK,
+        )?;
+
+        let preview_square = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, 29.0, 29.0),
+            Color::WHITE,
+        )?;
+
+        let font = graphics::Font::new(ctx, "/fonts/PressStart2P-Regular.ttf")?;
+
+        let level_text = Text::new(
+            // The font looks better in all caps, in my opinion.
+            TextFragment::new(format!(
+                "LEVEL: {}",
+                self.engine.level.separate_with_commas()
+            ))
+            .font(font)
+            .scale(28.0),
+        );
+
+        let lines_text = Text::new(
+            TextFragment::new(format!(
+                "LINES: \n{}",
+                self.engine.lines_cleared.separate_with_commas()
+            ))
+            .font(font)
+            .scale(28.0),
+        );
+
+        let score_text = Text::new(
+            TextFragment::new(format!(
+                "SCORE: \n{}",
+                self.engine.score.separate_with_commas()
+            ))
+            .font(font)
+            .scale(28.0),
+        );
+
+        // Only some scoring rules have grades, these are shown next to the score.
+        let grade_text = self
+            .engine
+            .scoring
+            .grade(self.engine.score)
+            .map(|grade| Text::new(TextFragment::new(grade).font(font).scale(28.0)));
+
+        let held_text = Text::new(TextFragment::new("HOLD:").font(font).scale(28.0));
+        let next_text = Text::new(TextFragment::new("NEXT:").font(font).scale(24.0));
+
+        let count_text = Text::new(
+            TextFragment::new(format!(
+                "{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}",
+                self.engine.piece_count[0].separate_with_commas(),
+                self.engine.piece_count[1].separate_with_commas(),
+                self.engine.piece_count[2].separate_with_commas(),
+                self.engine.piece_count[3].separate_with_commas(),
+                self.engine.piece_count[4].separate_with_commas(),
+                self.engine.piece_count[5].separate_with_commas(),
+                self.engine.piece_count[6].separate_with_commas(),
+            ))
+            .font(font)
+            .scale(24.0),
+        );
+
+        let clear_count = &self.engine.clear_count;
+
+        let line_count_text = Text::new(
+            TextFragment::new(format!(
+                "SINGLE:\n{}\n\nDOUBLE:\n{}\n\nTRIPLE:\n{}\n\nTETRIS:\n{}\n\nT-SPIN:\n{}\n\nB2B:\n{}\n\nCOMBO:\n{}\n\nPERFECT:\n{}",
+                clear_count.lines[0].separate_with_commas(),
+                clear_count.lines[1].separate_with_commas(),
+                clear_count.lines[2].separate_with_commas(),
+                clear_count.lines[3].separate_with_commas(),
+                clear_count.t_spins.separate_with_commas(),
+                clear_count.back_to_backs.separate_with_commas(),
+                clear_count.max_combo.separate_with_commas(),
+                clear_count.perfect_clears.separate_with_commas()
+            ))
+            .font(font)
+            .scale(14.0),
+        );
+
+        // Reaching the goal of the mode also ends the game, but you did not lose.
+        let game_over_text = Text::new(
+            TextFragment::new(if self.engine.finished {
+                "FINISHED!"
+            } else {
+                "GAME OVER!"
+            })
+            .font(font)
+            .scale(50.0),
+        );
+        let restart_key = first_key(&self.engine.config.controls, Control::Restart, "ENTER");
+        let pause_key = first_key(&self.engine.config.controls, Control::Pause, "ESCAPE");
+
+        let mut restart_text = Text::new(
+            TextFragment::new(format!("HOLD {} TO RESTART.\n", restart_key))
+                .font(font)
+                .scale(21.0),
+        );
+        restart_text.add(
+            TextFragment::new(format!("PRESS {} FOR THE MENU.", pause_key))
+                .font(font)
+                .scale(14.0),
+        );
+
+        // The clock of the game, Ultra counts down instead of up.
+        let mode_text = Text::new(
+            TextFragment::new(match self.engine.config.mode {
+                GameMode::Marathon => format!("MARATHON {}", frames_to_time(self.engine.frame)),
+                GameMode::Sprint => format!(
+                    "SPRINT {}\n{} LINES LEFT",
+                    frames_to_time(self.engine.frame),
+                    SPRINT_LINES.saturating_sub(self.engine.lines_cleared)
+                ),
+                GameMode::Ultra => format!(
+                    "ULTRA {}",
+                    frames_to_time(ULTRA_FRAMES.saturating_sub(self.engine.frame))
+                ),
+            })
+            .font(font)
+            .scale(14.0),
+        );
+
+        let seed_text = Text::new(
+            TextFragment::new(format!("SEED:\n{}", self.engine.seed))
+                .font(font)
+                .scale(21.0),
+        );
+
+        // How the game ended, like "LOCK OUT".
+        let top_out_text = self
+            .engine
+            .top_out
+            .map(|top_out| Text::new(TextFragment::new(top_out.name()).font(font).scale(21.0)));
+
+        // The shadow coordinates are the coordinates of the piece if it were dropped.
+        // So you can see where the piece will end up.
+        let shadow_coordinates = self.engine.shadow_piece();
+
+        let temp_piece_pos = self.engine.current_piece_cells();
+
+        let mut background_color = (77, 77, 204).into();
+
+        // If any locked piece reaches into the first five visible lines, or above them, the menu turns red.
+        if (0..self.engine.board.buffer + 5).any(|y| !self.engine.board.row_is_empty(y)) {
+            background_color = (204, 77, 77).into();
+        }
+
+        let menu_background = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(left, 0.0, sidebar_width(preview_count), WINDOW_HEIGHT),
+            background_color,
+        )?;
+
+        let board = self.engine.composite_board();
+
+        // Screen shakes move the whole board around a bit.
+        let (shake_x, shake_y) = self.animations.shake_offset();
+        let shake = Vec2::new(shake_x * cell, shake_y * cell);
+
+        // We stop drawing the board if you reach game over.
+        if !self.engine.game_over {
+            // Only the visible rows are drawn, the hidden buffer above them is off screen.
+            for y in board.visible_rows() {
+                let screen_y = (y - board.buffer) as f32 * cell;
+                // The locked rows above a line clear slide down into place, instead of jumping.
+                let falling_y = self.animations.row_offset(y).mul_add(-cell, screen_y);
+
+                for x in 0..board.width {
+                    if shadow_coordinates.contains(&(y, x)) {
+                        graphics::draw(
+                            ctx,
+                            &shadow_square_outline,
+                            graphics::DrawParam::default()
+                                .dest(shake + Vec2::new((x as f32) * cell, screen_y)),
+                        )?;
+                        graphics::draw(
+                            ctx,
+                            &shadow_square_fill,
+                            graphics::DrawParam::default()
+                                .dest(shake + Vec2::new((x as f32) * cell, screen_y)),
+                        )?;
+                    }
+
+                    if board.is_occupied(y, x) {
+                        let mut block_color = board.color(y, x);
+                        let is_piece = temp_piece_pos.contains(&(y, x));
+
+                        if !self.engine.config.colored_board && !is_piece {
+                            block_color = (255, 255, 255);
+                        }
+
+                        let block_y = if is_piece { screen_y } else { falling_y };
+
+                        graphics::draw(
+                            ctx,
+                            &square,
+                            graphics::DrawParam::default()
+                                .dest(shake + Vec2::new((x as f32) * cell, block_y))
+                                .color(block_color.into()),
+                        )?;
+                    }
+                }
+            }
+
+            self.draw_animations(ctx, shake)?;
+        } else {
+            graphics::draw(
+                ctx,
+                &game_over_text,
+                graphics::DrawParam::default().dest([5.0, 200.0]),
+            )?;
+            graphics::draw(
+                ctx,
+                &restart_text,
+                graphics::DrawParam::default().dest([15.0, 255.0]),
+            )?;
+            graphics::draw(
+                ctx,
+                &seed_text,
+                graphics::DrawParam::default().dest([15.0, 310.0]),
+            )?;
+
+            if let Some(top_out_text) = top_out_text {
+                graphics::draw(
+                    ctx,
+                    &top_out_text,
+                    graphics::DrawParam::default().dest([15.0, 390.0]),
+                )?;
+            }
+
+            self.draw_high_scores(ctx, font)?;
+        }
+
+        graphics::draw(ctx, &menu_background, graphics::DrawParam::default())?;
+
+        graphics::draw(
+            ctx,
+            &level_text,
+            graphics::DrawParam::default().dest([left + 10.0, 10.0]),
+        )?;
+
+        graphics::draw(
+            ctx,
+            &lines_text,
+            graphics::DrawParam::default().dest([left + 10.0, 55.0]),
+        )?;
+
+        graphics::draw(
+            ctx,
+            &score_text,
+            graphics::DrawParam::default().dest([left + 10.0, 155.0]),
+        )?;
+
+        if let Some(grade_text) = grade_text {
+            graphics::draw(
+                ctx,
+                &grade_text,
+                graphics::DrawParam::default().dest([left + 210.0, 155.0]),
+            )?;
+        }
+
+        graphics::draw(
+            ctx,
+            &piece_windows,
+            graphics::DrawParam::default().dest([left + 10.0, 250.0]),
+        )?;
+
+        graphics::draw(
+            ctx,
+            &held_text,
+            graphics::DrawParam::default().dest([left + 10.0, 250.0]),
+        )?;
+
+        graphics::draw(ctx, &count_window, graphics::DrawParam::default())?;
+
+        graphics::draw(
+            ctx,
+            &count_text,
+            graphics::DrawParam::default().dest([left + 80.0, 455.0]),
+        )?;
+
+        // We draw miniature versions of the pieces in the menu, for the piece counts.
+        let all_pieces = PieceType::ALL.map(|piece_type| self.engine.new_piece(piece_type));
+
+        for (x, piece) in all_pieces.iter().enumerate() {
+            for block in &piece.orientations[0] {
+                graphics::draw(
+                    ctx,
+                    &mini_square,
+                    graphics::DrawParam::default()
+                        .dest([
+                            (block.1 as f32).mul_add(12.0, left + 15.0),
+                            (x as f32).mul_add(48.0, (block.0 as f32).mul_add(12.0, 455.0)),
+                        ])
+                        .color(piece.color.into()),
+                )?;
+            }
+        }
+
+        // The upcoming pieces are shown in their own column, top to bottom.
+        if preview_count > 0 {
+            graphics::draw(
+                ctx,
+                &preview_window,
+                graphics::DrawParam::default().dest([left + 310.0, 10.0]),
+            )?;
+
+            graphics::draw(
+                ctx,
+                &next_text,
+                graphics::DrawParam::default().dest([left + 315.0, 15.0]),
+            )?;
+
+            for (i, piece) in self.engine.previews().enumerate() {
+                for block in &piece.orientations[0] {
+                    graphics::draw(
+                        ctx,
+                        &preview_square,
+                        graphics::DrawParam::default()
+                            .dest([
+                                (block.1 as f32).mul_add(30.0, left + 315.0),
+                                (i as f32).mul_add(100.0, (block.0 as f32).mul_add(30.0, 50.0)),
+                            ])
+                            .color(piece.color.into()),
+                    )?;
+                }
+            }
+        }
+
+        graphics::draw(
+            ctx,
+            &line_count_text,
+            graphics::DrawParam::default().dest([left + 165.0, 455.0]),
+        )?;
+
+        if self.engine.held_piece.is_some() {
+            let mut held_color = (60, 60, 60).into();
+
+            // If you cant switch the held piece, we will color it grey,
+            // otherwise it will be the actual piece color.
+            if self.engine.can_swap {
+                held_color = self.engine.held_piece.clone().unwrap().color.into()
+            }
+
+            for block in &self.engine.held_piece.clone().unwrap().orientations[0] {
+                graphics::draw(
+                    ctx,
+                    &held_square,
+                    graphics::DrawParam::default()
+                        .dest([
+                            (block.1 as f32).mul_add(50.0, left + 20.0),
+                            (block.0 as f32).mul_add(50.0, 300.0),
+                        ])
+                        .color(held_color),
+                )?;
+            }
+        }
+
+        if let Some((clear, frames)) = &self.last_clear {
+            if *frames > 0 {
+                let clear_text = Text::new(TextFragment::new(clear.name()).font(font).scale(24.0));
+
+                graphics::draw(
+                    ctx,
+                    &clear_text,
+                    graphics::DrawParam::default().dest([left + 10.0, 810.0]),
+                )?;
+            }
+        }
+
+        graphics::draw(
+            ctx,
+            &mode_text,
+            graphics::DrawParam::default().dest([left + 10.0, 900.0]),
+        )?;
+
+        if let Some(playback) = &self.playback {
+            let replay_text = Text::new(
+                TextFragment::new(format!(
+                    "REPLAY {} / {}\nSPEED: x{}",
+                    frames_to_time(self.engine.frame),
+                    frames_to_time(playback.replay.frames),
+                    self.playback_speed
+                ))
+                .font(font)
+                .scale(14.0),
+            );
+
+            graphics::draw(
+                ctx,
+                &replay_text,
+                graphics::DrawParam::default().dest([left + 10.0, 950.0]),
+            )?;
+        }
+
+        if self.paused {
+            let paused_text = Text::new(TextFragment::new("PAUSED").font(font).scale(75.0));
+
+            graphics::draw(
+                ctx,
+                &paused_text,
+                graphics::DrawParam::default().dest([30.0, 200.0]),
+            )?;
+
+            if self.playback.is_none() {
+                let menu_text = Text::new(
+                    TextFragment::new(format!("PRESS {} FOR THE MENU.", restart_key))
+                        .font(font)
+                        .scale(14.0),
+                );
+
+                graphics::draw(
+                    ctx,
+                    &menu_text,
+                    graphics::DrawParam::default().dest([30.0, 290.0]),
+                )?;
+            }
+        }
+
+        graphics::present(ctx)?;
+
+        graphics::clear_font_cache(ctx);
+
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut ggez::Context,
+        keycode: event::KeyCode,
+        _keymods: event::KeyMods,
+        repeat: bool,
+    ) {
+        if self.menu.screen != Screen::Playing {
+            match self.menu.key_down(keycode) {
+                Some(MenuAction::Start(mode)) => self.start_game(mode),
+                Some(MenuAction::Quit) => event::quit(ctx),
+                None => (),
+            }
+            return;
+        }
+
+        // While you type in your name for the high scores, the keys are only for that.
+        if let Some(name) = &mut self.name_entry {
+            match keycode {
+                event::KeyCode::Return | event::KeyCode::NumpadEnter => self.submit_high_score(),
+                event::KeyCode::Back => {
+                    name.pop();
+                }
+                // Skips the high score, without saving it.
+                event::KeyCode::Escape => self.name_entry = None,
+                _ => (),
+            }
+            return;
+        }
+
+        // Replays have their own keys, the game keys do nothing while watching one.
+        if self.playback.is_some() {
+            self.playback_key_down(keycode);
+            return;
+        }
+
+        let control = match self.bindings.get(&keycode) {
+            Some(&control) => control,
+            None => return,
+        };
+
+        // If the game is paused, we dont listen to any keystrokes except for the pause keys.
+        if !self.paused {
+            // The engine takes care of repeating held keys itself,
+            // so we ignore the key repeats of the operating system.
+            if let Some(action) = control.action() {
+                if !repeat {
+                    self.recorder.press(&mut self.engine, action);
+                }
+                return;
+            }
+
+            match control {
+                Control::Restart if self.engine.game_over && repeat => self.reset_game(),
+                Control::Pause => {
+                    // No real reason to pause on the game over screen, so this goes back to the menu instead.
+                    if self.engine.game_over {
+                        self.quit_to_menu();
+                    } else {
+                        self.paused = true;
+                        self.recorder.release_all(&mut self.engine);
+                    }
+                }
+                _ => (),
+            }
+        } else {
+            match control {
+                Control::Pause => self.paused = false,
+                Control::Restart => self.quit_to_menu(),
+                _ => (),
+            }
+        }
+    }
+
+    fn key_up_event(
+        &mut self,
+        _ctx: &mut ggez::Context,
+        keycode: event::KeyCode,
+        _keymods: event::KeyMods,
+    ) {
+        if self.playback.is_some() {
+            return;
+        }
+
+        if let Some(action) = self.bindings.get(&keycode).and_then(|c| c.action()) {
+            self.recorder.release(&mut self.engine, action);
+        }
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut ggez::Context, character: char) {
+        if let Some(name) = &mut self.name_entry {
+            // The font only has the ASCII characters, and looks better in all caps.
+            if (character.is_ascii_graphic() || character == ' ')
+                && name.chars().count() < MAX_NAME_LENGTH
+            {
+                name.push(character.to_ascii_uppercase());
+            }
+        }
+    }
+}
+
+/// Looks up the keys of the controls, key names that do not exist are skipped with a warning.
+fn key_bindings(controls: &Controls) -> HashMap<event::KeyCode, Control> {
+    let mut bindings = HashMap::new();
+
+    for (name, control) in controls.bindings() {
+        match key_from_name(&name) {
+            Some(keycode) => {
+                bindings.insert(keycode, control);
+            }
+            None => println!(
+                "There is no key called \"{}\", it will not be bound to {}.",
+                name,
+                control.name()
+            ),
+        }
+    }
+
+    bindings
+}
+
+/// The first key of the control in upper case, for the texts that tell you which key to press.
+fn first_key(controls: &Controls, control: Control, fallback: &str) -> String {
+    controls
+        .keys(control)
+        .first()
+        .map_or_else(|| fallback.to_string(), |key| key.to_uppercase())
+}
+
+/// How big every cell of the board is drawn, so that the whole board fits the window.
+fn cell_size(board: &Board) -> f32 {
+    (WINDOW_HEIGHT / board.height as f32)
+        .min(MAX_BOARD_WIDTH / board.width as f32)
+        .min(MAX_CELL_SIZE)
+        .floor()
+}
+
+/// How wide the board is drawn, the sidebar starts right after it.
+fn board_width(board: &Board) -> f32 {
+    cell_size(board) * board.width as f32
+}
+
+/// The width of the sidebar, it grows by a column if there are upcoming pieces to show.
+fn sidebar_width(preview_count: usize) -> f32 {
+    if preview_count > 0 {
+        450.0
+    } else {
+        300.0
+    }
+}
+
+pub fn run() -> GameResult {
+    let game = MainGame::new()?;
+
+    let preview_count = game.engine.previews().count();
+
+    let window = ggez::conf::WindowMode::default().dimensions(
+        board_width(&game.engine.board) + sidebar_width(preview_count),
+        WINDOW_HEIGHT,
+    );
+
+    let mut asset_path = path::PathBuf::from("./");
+    asset_path.push("resources");
+
+    // So the game also finds its resources when it is not started from its own folder.
+    let exe_asset_path = executable_dir().map(|dir| dir.join("resources"));
+
+    let window_setup = ggez::conf::WindowSetup::default()
+        .title("Tetris!")
+        .srgb(true)
+        .icon("/icons/icon.png");
+
+    let mut context_builder = ggez::ContextBuilder::new("Tetris", "atomflunder")
+        .window_setup(window_setup)
+        .window_mode(window)
+        .add_resource_path(asset_path);
+
+    if let Some(exe_asset_path) = exe_asset_path {
+        context_builder = context_builder.add_resource_path(exe_asset_path);
+    }
+
+    let (ctx, event_loop) = context_builder.build()?;
+
+    event::run(ctx, event_loop, game)
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+use super::engine::FRAMES_PER_SECOND;
+
+/// The fastest possible gravity, 20 rows per frame, which is the whole board at once.
+/// At this speed pieces spawn at the bottom and fall back down instantly after every move, like in TGM.
+pub const MAX_GRAVITY: f64 = 20.0;
+
+// How many frames a piece takes to fall one row in NES Tetris, on levels 0 to 29.
+// Everything after level 29 falls a row every frame.
+const NES_FRAMES_PER_ROW: [u32; 29] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+];
+
+// The internal gravity of Tetris The Grand Master, in 1/256 rows per frame, from the given TGM level on.
+const TGM_GRAVITY: [(u128, u32); 30] = [
+    (0, 4),
+    (30, 6),
+    (35, 8),
+    (40, 10),
+    (50, 12),
+    (60, 16),
+    (70, 32),
+    (80, 48),
+    (90, 64),
+    (100, 80),
+    (120, 96),
+    (140, 112),
+    (160, 128),
+    (170, 144),
+    (200, 4),
+    (220, 32),
+    (230, 64),
+    (233, 96),
+    (236, 128),
+    (239, 160),
+    (243, 192),
+    (247, 224),
+    (251, 256),
+    (300, 512),
+    (330, 768),
+    (360, 1024),
+    (400, 1280),
+    (420, 1024),
+    (450, 768),
+    (500, 5120),
+];
+
+// Our levels go up every 10 lines, every one of them counts as this many TGM levels.
+const TGM_LEVELS_PER_LEVEL: u128 = 50;
+
+/// How fast the pieces fall on every level, selectable in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GravityCurve {
+    // This game's own curve, 1 row per second faster every 5 levels.
+    Classic,
+    // The formula of the modern games, reaching 20G at level 19.
+    Guideline,
+    // The frame table of NES Tetris, where our level 1 is the NES level 0.
+    Nes,
+    // The internal gravity of Tetris The Grand Master, reaching 20G at level 11.
+    Tgm,
+    // The table in `custom_gravity`.
+    Custom,
+}
+
+impl GravityCurve {
+    /// How many rows per frame the piece falls on the given level, this is usually much less than 1.
+    /// Never more than `MAX_GRAVITY`.
+    pub fn rows_per_frame(self, level: u128, custom: &[f64]) -> f64 {
+        let level = level.max(1);
+
+        let gravity = match self {
+            Self::Classic => (level as f64 / 5.0).ceil() / f64::from(FRAMES_PER_SECOND),
+            Self::Guideline => {
+                // The formula gives the seconds per row, and stops making sense after level 20.
+                let level = level.min(20) as f64;
+                let seconds_per_row = (0.8 - (level - 1.0) * 0.007).powf(level - 1.0);
+
+                1.0 / (seconds_per_row * f64::from(FRAMES_PER_SECOND))
+            }
+            Self::Nes => {
+                let frames = NES_FRAMES_PER_ROW
+                    .get(level as usize - 1)
+                    .copied()
+                    .unwrap_or(1);
+
+                1.0 / f64::from(frames)
+            }
+            Self::Tgm => {
+                let tgm_level = (level - 1) * TGM_LEVELS_PER_LEVEL;
+
+                let internal = TGM_GRAVITY
+                    .iter()
+                    .rev()
+                    .find(|(from, _)| tgm_level >= *from)
+                    .map_or(4, |(_, gravity)| *gravity);
+
+                f64::from(internal) / 256.0
+            }
+            // The last level of the table is used for every level after it.
+            Self::Custom => custom
+                .get(level as usize - 1)
+                .or_else(|| custom.last())
+                .copied()
+                .unwrap_or(1.0 / f64::from(FRAMES_PER_SECOND)),
+        };
+
+        gravity.clamp(0.0, MAX_GRAVITY)
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::config::{config_dir, setting_name, Config};
+use super::engine::{frames_to_time, Engine};
+use super::mode::GameMode;
+
+/// How many scores every table keeps.
+pub const MAX_HIGH_SCORES: usize = 10;
+
+/// The longest name you can enter for a high score.
+pub const MAX_NAME_LENGTH: usize = 10;
+
+/// A single finished game in a high score table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u128,
+    pub lines: u128,
+    pub level: u128,
+    // How many frames the game lasted.
+    pub frames: u64,
+    // How many of every piece spawned, in the same order as in the sidebar.
+    pub pieces: Vec<u128>,
+    // When the game was played, in seconds since 1970.
+    pub date: u64,
+    pub seed: u64,
+}
+
+impl HighScore {
+    /// The high score of the game the engine just played.
+    pub fn from_engine(engine: &Engine, name: &str) -> Self {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+
+        Self {
+            name: name.to_string(),
+            score: engine.score,
+            lines: engine.lines_cleared,
+            level: engine.level,
+            frames: engine.frame,
+            pieces: engine.piece_count.clone(),
+            date,
+            seed: engine.seed,
+        }
+    }
+
+    /// How long the game lasted, like "12:05".
+    pub fn time(&self) -> String {
+        frames_to_time(self.frames)
+    }
+
+    /// The day the game was played, like "2022-06-14", in UTC.
+    pub fn day(&self) -> String {
+        let (year, month, day) = civil_date(self.date / 86_400);
+
+        format!("{}-{:02}-{:02}", year, month, day)
+    }
+
+    /// How many pieces spawned in total.
+    pub fn total_pieces(&self) -> u128 {
+        self.pieces.iter().sum()
+    }
+}
+
+/// Every high score table, one for every mode and ruleset.
+/// Games are only compared against games with the same rules, a 20G game is not a fair fight for a classic one.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    // The name of the ruleset, and its games from the best to the worst.
+    pub tables: BTreeMap<String, Vec<HighScore>>,
+    // The last name that was entered, so you do not have to type it again every game.
+    pub last_name: String,
+}
+
+impl HighScores {
+    /// Loads the high scores from the data file.
+    /// If there is none yet, or it is broken, you start with empty tables.
+    pub fn load() -> Self {
+        let path = match high_scores_path() {
+            Some(path) => path,
+            None => return Self::default(),
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default(),
+        };
+
+        serde_json::from_str(&contents).unwrap_or_else(|error| {
+            println!(
+                "{}: The high scores could not be read, {}\nStarting with empty high scores...",
+                path.display(),
+                error
+            );
+            Self::default()
+        })
+    }
+
+    /// Saves the high scores to the data file, next to the config file.
+    pub fn save(&self) -> io::Result<()> {
+        let path = high_scores_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "There is no config folder."))?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents = serde_json::to_string_pretty(self)?;
+
+        fs::write(path, contents)
+    }
+
+    /// The games of the ruleset, from the best to the worst.
+    pub fn table(&self, ruleset: &str) -> &[HighScore] {
+        self.tables.get(ruleset).map_or(&[], Vec::as_slice)
+    }
+
+    /// Checks if the game the engine played would make it into the table of its ruleset.
+    /// Sprint games only count if all of the lines were cleared.
+    pub fn qualifies(&self, engine: &Engine) -> bool {
+        let mode = engine.config.mode;
+        let high_score = HighScore::from_engine(engine, "");
+        let table = self.table(&ruleset(&engine.config));
+
+        let counts = if mode.ranks_by_time() {
+            engine.finished
+        } else {
+            engine.score > 0
+        };
+
+        counts
+            && (table.len() < MAX_HIGH_SCORES || table.iter().any(|s| beats(mode, &high_score, s)))
+    }
+
+    /// Adds the game the engine played to the table of its ruleset, with your name.
+    /// Returns its place counting from 0, or None if it did not make it into the table.
+    pub fn insert(&mut self, engine: &Engine, name: &str) -> Option<usize> {
+        if !self.qualifies(engine) {
+            return None;
+        }
+
+        let mode = engine.config.mode;
+        let high_score = HighScore::from_engine(engine, name);
+
+        self.last_name = name.to_string();
+
+        let table = self.tables.entry(ruleset(&engine.config)).or_default();
+
+        // Ties go below the older games.
+        let place = table
+            .iter()
+            .position(|s| beats(mode, &high_score, s))
+            .unwrap_or(table.len());
+
+        table.insert(place, high_score);
+        table.truncate(MAX_HIGH_SCORES);
+
+        Some(place)
+    }
+}
+
+/// Checks if the game is ranked higher than the other one, the faster one in Sprint and the higher score otherwise.
+fn beats(mode: GameMode, high_score: &HighScore, other: &HighScore) -> bool {
+    if mode.ranks_by_time() {
+        high_score.frames < other.frames
+    } else {
+        high_score.score > other.score
+    }
+}
+
+/// The name of the high score table the game belongs in.
+/// Games are only ranked against games of the same mode, with the same rotation system, randomizer, gravity,
+/// scoring and board size.
+pub fn ruleset(config: &Config) -> String {
+    format!(
+        "{} {} {} {} {} {}x{}",
+        setting_name(&config.mode),
+        setting_name(&config.rotation_system),
+        setting_name(&config.randomizer),
+        setting_name(&config.gravity),
+        setting_name(&config.scoring),
+        config.board_width,
+        config.board_height
+    )
+}
+
+/// Where the high scores are saved, next to the config file in the config directory.
+pub fn high_scores_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("highscores.json"))
+}
+
+/// The year, month and day of the day that is so many days after 1970-01-01.
+/// This is Howard Hinnant's `civil_from_days`, so we do not need a whole date library for a date.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Eras of 400 years start on March 1st, so the leap day is the last day of the year.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished_game(mode: GameMode, score: u128, frames: u64) -> Engine {
+        let mut engine = Engine::new(Config {
+            mode,
+            seed: Some(1),
+            ..Config::default()
+        });
+
+        engine.score = score;
+        engine.frame = frames;
+        engine.game_over = true;
+        engine.finished = mode.ranks_by_time();
+
+        engine
+    }
+
+    #[test]
+    fn every_mode_and_ruleset_has_its_own_table() {
+        let mut high_scores = HighScores::default();
+        let marathon = finished_game(GameMode::Marathon, 100, 600);
+        let ultra = finished_game(GameMode::Ultra, 50, 600);
+        let mut wide = finished_game(GameMode::Marathon, 70, 600);
+        wide.config.board_width = 20;
+
+        assert_eq!(high_scores.insert(&marathon, "A"), Some(0));
+        assert_eq!(high_scores.insert(&ultra, "B"), Some(0));
+        assert_eq!(high_scores.insert(&wide, "C"), Some(0));
+
+        assert_eq!(high_scores.tables.len(), 3);
+        assert_eq!(high_scores.table(&ruleset(&marathon.config))[0].name, "A");
+        assert_eq!(high_scores.last_name, "C");
+    }
+
+    #[test]
+    fn tables_keep_the_best_games() {
+        let mut high_scores = HighScores::default();
+
+        for score in 1..=MAX_HIGH_SCORES as u128 {
+            high_scores.insert(&finished_game(GameMode::Marathon, score * 10, 600), "A");
+        }
+
+        let worse = finished_game(GameMode::Marathon, 5, 600);
+        let better = finished_game(GameMode::Marathon, 55, 600);
+
+        assert!(!high_scores.qualifies(&worse));
+        assert_eq!(high_scores.insert(&better, "B"), Some(5));
+
+        let table = high_scores.table(&ruleset(&better.config));
+        assert_eq!(table.len(), MAX_HIGH_SCORES);
+        assert_eq!(table.last().unwrap().score, 20);
+    }
+
+    #[test]
+    fn sprint_ranks_by_time() {
+        let mut high_scores = HighScores::default();
+        let slow = finished_game(GameMode::Sprint, 5000, 6000);
+        let fast = finished_game(GameMode::Sprint, 100, 3000);
+        let mut unfinished = finished_game(GameMode::Sprint, 9000, 1000);
+        unfinished.finished = false;
+
+        high_scores.insert(&slow, "A");
+
+        assert_eq!(high_scores.insert(&fast, "B"), Some(0));
+        assert!(!high_scores.qualifies(&unfinished));
+    }
+}
@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+/// Everything the player can do to the current piece.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateCounterClockwise,
+    Rotate180,
+    Hold,
+}
+
+/// Keeps track of which actions are held down, and how long the left/right movement has been held,
+/// so that holding a direction moves the piece with the configured DAS and ARR,
+/// instead of depending on the keyboard repeat rate of the operating system.
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    // The held actions, in the order they were pressed.
+    held: Vec<Action>,
+    // How many frames the current direction has been held for, this is the "DAS charge".
+    das_frames: u32,
+    // How many frames it has been since the last automatic move.
+    arr_frames: u32,
+    // The held actions that were pressed while waiting for the next piece, in the order they were pressed.
+    // Only these count for the Initial Rotation and Hold Systems, not keys still held from the last piece.
+    initial: Vec<Action>,
+}
+
+impl InputState {
+    /// Marks the action as held, returns false if it already was.
+    pub fn press(&mut self, action: Action) -> bool {
+        if self.is_held(action) {
+            return false;
+        }
+
+        self.held.push(action);
+
+        // Pressing a direction always starts charging DAS from the beginning.
+        if action == Action::MoveLeft || action == Action::MoveRight {
+            self.das_frames = 0;
+            self.arr_frames = 0;
+        }
+
+        true
+    }
+
+    /// Marks the action as not held anymore.
+    pub fn release(&mut self, action: Action) {
+        let was_direction = self.direction() == Some(action);
+
+        self.held.retain(|&a| a != action);
+        self.initial.retain(|&a| a != action);
+
+        // If you let go of a direction while still holding the other one,
+        // the other one has to charge DAS again.
+        if was_direction {
+            self.das_frames = 0;
+            self.arr_frames = 0;
+        }
+    }
+
+    /// Lets go of every action, for example when the game is paused.
+    pub fn release_all(&mut self) {
+        self.held.clear();
+        self.initial.clear();
+        self.das_frames = 0;
+        self.arr_frames = 0;
+    }
+
+    /// The held actions, in the order they were pressed.
+    pub fn held(&self) -> &[Action] {
+        &self.held
+    }
+
+    pub fn is_held(&self, action: Action) -> bool {
+        self.held.contains(&action)
+    }
+
+    /// The direction the piece should be moving in, if both are held the last pressed one wins.
+    pub fn direction(&self) -> Option<Action> {
+        self.held
+            .iter()
+            .rev()
+            .find(|&&a| a == Action::MoveLeft || a == Action::MoveRight)
+            .copied()
+    }
+
+    /// Marks the held action as pressed while waiting for the next piece.
+    pub fn press_initial(&mut self, action: Action) {
+        if self.is_held(action) && !self.initial.contains(&action) {
+            self.initial.push(action);
+        }
+    }
+
+    /// Checks if the action was pressed while waiting for the next piece, and is still held.
+    pub fn is_initial(&self, action: Action) -> bool {
+        self.initial.contains(&action)
+    }
+
+    /// Forgets the actions pressed while waiting, once the next piece spawned.
+    /// Holding them any longer does not rotate or hold the piece after that one.
+    pub fn clear_initial(&mut self) {
+        self.initial.clear();
+    }
+
+    /// The rotation to spawn the next piece in, if a rotation was pressed while waiting for it, for the Initial Rotation System.
+    /// If more than one is held the last pressed one wins, just like with the directions.
+    pub fn initial_rotation(&self) -> Option<Action> {
+        self.initial
+            .iter()
+            .rev()
+            .find(|&&a| {
+                a == Action::RotateClockwise
+                    || a == Action::RotateCounterClockwise
+                    || a == Action::Rotate180
+            })
+            .copied()
+    }
+
+    /// Charges DAS by a frame and returns how many times the piece should be moved automatically this frame.
+    /// Both `das` and `arr` are in frames, an ARR of 0 returns `u32::MAX`, moving the piece all the way to the wall.
+    pub fn tick(&mut self, das: u32, arr: u32) -> u32 {
+        if self.direction().is_none() {
+            return 0;
+        }
+
+        self.das_frames = self.das_frames.saturating_add(1);
+
+        match self.das_frames.cmp(&das) {
+            Ordering::Less => 0,
+            // DAS is fully charged this frame, so the first automatic move happens right away.
+            Ordering::Equal => {
+                self.arr_frames = 0;
+
+                if arr == 0 {
+                    u32::MAX
+                } else {
+                    1
+                }
+            }
+            Ordering::Greater => {
+                if arr == 0 {
+                    return u32::MAX;
+                }
+
+                self.arr_frames += 1;
+
+                if self.arr_frames >= arr {
+                    self.arr_frames = 0;
+                    1
+                } else {
+                    0
+                }
+            }
+        }
+    }
+}
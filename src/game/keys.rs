@@ -0,0 +1,104 @@
+use ggez::event::KeyCode;
+
+/// Gets the key with the given name, as used in the controls of the config.
+/// The names are the same as the `KeyCode` names, but upper/lower case does not matter.
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    let key = match name.to_ascii_lowercase().as_str() {
+        "a" => KeyCode::A,
+        "b" => KeyCode::B,
+        "c" => KeyCode::C,
+        "d" => KeyCode::D,
+        "e" => KeyCode::E,
+        "f" => KeyCode::F,
+        "g" => KeyCode::G,
+        "h" => KeyCode::H,
+        "i" => KeyCode::I,
+        "j" => KeyCode::J,
+        "k" => KeyCode::K,
+        "l" => KeyCode::L,
+        "m" => KeyCode::M,
+        "n" => KeyCode::N,
+        "o" => KeyCode::O,
+        "p" => KeyCode::P,
+        "q" => KeyCode::Q,
+        "r" => KeyCode::R,
+        "s" => KeyCode::S,
+        "t" => KeyCode::T,
+        "u" => KeyCode::U,
+        "v" => KeyCode::V,
+        "w" => KeyCode::W,
+        "x" => KeyCode::X,
+        "y" => KeyCode::Y,
+        "z" => KeyCode::Z,
+        "key0" => KeyCode::Key0,
+        "key1" => KeyCode::Key1,
+        "key2" => KeyCode::Key2,
+        "key3" => KeyCode::Key3,
+        "key4" => KeyCode::Key4,
+        "key5" => KeyCode::Key5,
+        "key6" => KeyCode::Key6,
+        "key7" => KeyCode::Key7,
+        "key8" => KeyCode::Key8,
+        "key9" => KeyCode::Key9,
+        "f1" => KeyCode::F1,
+        "f2" => KeyCode::F2,
+        "f3" => KeyCode::F3,
+        "f4" => KeyCode::F4,
+        "f5" => KeyCode::F5,
+        "f6" => KeyCode::F6,
+        "f7" => KeyCode::F7,
+        "f8" => KeyCode::F8,
+        "f9" => KeyCode::F9,
+        "f10" => KeyCode::F10,
+        "f11" => KeyCode::F11,
+        "f12" => KeyCode::F12,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "escape" => KeyCode::Escape,
+        "return" => KeyCode::Return,
+        "space" => KeyCode::Space,
+        "tab" => KeyCode::Tab,
+        "back" => KeyCode::Back,
+        "insert" => KeyCode::Insert,
+        "delete" => KeyCode::Delete,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "lalt" => KeyCode::LAlt,
+        "ralt" => KeyCode::RAlt,
+        "lcontrol" => KeyCode::LControl,
+        "rcontrol" => KeyCode::RControl,
+        "lshift" => KeyCode::LShift,
+        "rshift" => KeyCode::RShift,
+        "numpad0" => KeyCode::Numpad0,
+        "numpad1" => KeyCode::Numpad1,
+        "numpad2" => KeyCode::Numpad2,
+        "numpad3" => KeyCode::Numpad3,
+        "numpad4" => KeyCode::Numpad4,
+        "numpad5" => KeyCode::Numpad5,
+        "numpad6" => KeyCode::Numpad6,
+        "numpad7" => KeyCode::Numpad7,
+        "numpad8" => KeyCode::Numpad8,
+        "numpad9" => KeyCode::Numpad9,
+        "numpadenter" => KeyCode::NumpadEnter,
+        "numpadadd" => KeyCode::NumpadAdd,
+        "numpadsubtract" => KeyCode::NumpadSubtract,
+        "comma" => KeyCode::Comma,
+        "period" => KeyCode::Period,
+        "slash" => KeyCode::Slash,
+        "backslash" => KeyCode::Backslash,
+        "semicolon" => KeyCode::Semicolon,
+        "apostrophe" => KeyCode::Apostrophe,
+        "minus" => KeyCode::Minus,
+        "equals" => KeyCode::Equals,
+        "lbracket" => KeyCode::LBracket,
+        "rbracket" => KeyCode::RBracket,
+        "grave" => KeyCode::Grave,
+        _ => return None,
+    };
+
+    Some(key)
+}
@@ -0,0 +1,507 @@
+use ggez::{
+    event::KeyCode,
+    graphics::{self, Color, Text, TextFragment},
+    GameResult,
+};
+use thousands::Separable;
+
+use super::config::{load_config, save_settings, Config};
+use super::controls::{Control, ControlsPreset};
+use super::highscores::{ruleset, HighScore, HighScores};
+use super::keys::key_from_name;
+use super::mode::GameMode;
+use super::settings::Setting;
+
+/// The color of the selected menu entry, your new high score and the name you type in for it.
+pub const HIGHLIGHT_COLOR: Color = Color {
+    r: 1.0,
+    g: 0.85,
+    b: 0.2,
+    a: 1.0,
+};
+
+// The entries of the title menu, in order.
+const TITLE_ENTRIES: [&str; 5] = ["PLAY", "HIGH SCORES", "SETTINGS", "CREDITS", "QUIT"];
+
+const CREDITS: &str = "TETRIS-RUST\n\n\
+    MADE BY ATOMFLUNDER\n\n\
+    WRITTEN IN RUST,\nUSING GGEZ.\n\n\
+    FONT: PRESS START 2P\nBY CODEMAN38.\n\n\
+    TETRIS IS A TRADEMARK\nOF THE TETRIS COMPANY.\n\n\
+    LICENSED UNDER THE\nMIT LICENSE.";
+
+/// Everything the game can show, the menus and the game itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Screen {
+    Title,
+    ModeSelect,
+    Settings,
+    // The key bindings, part of the settings.
+    Controls,
+    HighScores,
+    Credits,
+    Playing,
+}
+
+/// What the game has to do after a key was pressed in the menus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    Start(GameMode),
+    Quit,
+}
+
+/// The menus around the game. They only use the arrow keys, Enter, Backspace and Escape,
+/// so you can always find your way around, even with broken key bindings.
+pub struct Menu {
+    pub screen: Screen,
+    // Which entry of the current menu is selected.
+    pub cursor: usize,
+    // The settings while you change them, they are only used once they are saved.
+    pub config: Config,
+    // The control that waits for you to press its new key, in the controls menu.
+    pub rebinding: Option<Control>,
+    // The high scores of which mode are shown.
+    pub high_score_mode: GameMode,
+    // What happened when the settings were saved, or why they could not be.
+    pub message: Option<String>,
+}
+
+impl Menu {
+    pub fn new(config: Config, screen: Screen) -> Self {
+        Self {
+            screen,
+            cursor: 0,
+            high_score_mode: config.mode,
+            config,
+            rebinding: None,
+            message: None,
+        }
+    }
+
+    /// Switches to another screen, with the first entry selected.
+    pub fn open(&mut self, screen: Screen) {
+        self.screen = screen;
+        self.cursor = 0;
+        self.rebinding = None;
+        self.message = None;
+    }
+
+    /// How many entries the current menu has, for moving the cursor around.
+    fn entry_count(&self) -> usize {
+        match self.screen {
+            Screen::Title => TITLE_ENTRIES.len(),
+            // Every mode and "BACK".
+            Screen::ModeSelect => GameMode::ALL.len() + 1,
+            // Every setting, "CONTROLS", "SAVE" and "BACK".
+            Screen::Settings => Setting::ALL.len() + 3,
+            // The preset, every control and "BACK".
+            Screen::Controls => Control::ALL.len() + 2,
+            Screen::HighScores | Screen::Credits | Screen::Playing => 1,
+        }
+    }
+
+    /// Handles a key press on one of the menu screens.
+    /// Returns what the game has to do, if it is more than moving around in the menus.
+    pub fn key_down(&mut self, keycode: KeyCode) -> Option<MenuAction> {
+        if let Some(control) = self.rebinding.take() {
+            self.bind(control, keycode);
+            return None;
+        }
+
+        let count = self.entry_count();
+
+        match keycode {
+            KeyCode::Up => self.cursor = (self.cursor + count - 1) % count,
+            KeyCode::Down => self.cursor = (self.cursor + 1) % count,
+            KeyCode::Left => self.change(false),
+            KeyCode::Right => self.change(true),
+            KeyCode::Back => self.reset_control(),
+            KeyCode::Escape => self.back(),
+            KeyCode::Return | KeyCode::NumpadEnter => return self.select(),
+            _ => (),
+        }
+
+        None
+    }
+
+    /// Goes back to the screen before, unsaved settings are thrown away.
+    fn back(&mut self) {
+        match self.screen {
+            Screen::Controls => {
+                self.open(Screen::Settings);
+                self.cursor = Setting::ALL.len();
+            }
+            Screen::Title | Screen::Playing => (),
+            _ => self.open(Screen::Title),
+        }
+    }
+
+    /// Does whatever the selected entry does.
+    fn select(&mut self) -> Option<MenuAction> {
+        let cursor = self.cursor;
+
+        match self.screen {
+            Screen::Title => match TITLE_ENTRIES[cursor] {
+                "PLAY" => {
+                    self.open(Screen::ModeSelect);
+                    self.cursor = GameMode::ALL
+                        .iter()
+                        .position(|&mode| mode == self.config.mode)
+                        .unwrap_or(0);
+                }
+                "HIGH SCORES" => self.open(Screen::HighScores),
+                "SETTINGS" => {
+                    // Starts from the file, in case you changed it by hand in the meantime.
+                    self.config = load_config();
+                    self.open(Screen::Settings);
+                }
+                "CREDITS" => self.open(Screen::Credits),
+                _ => return Some(MenuAction::Quit),
+            },
+            Screen::ModeSelect => match GameMode::ALL.get(cursor) {
+                Some(&mode) => {
+                    self.config.mode = mode;
+                    return Some(MenuAction::Start(mode));
+                }
+                None => self.back(),
+            },
+            Screen::Settings => match cursor.checked_sub(Setting::ALL.len()) {
+                // Enter flips switches just like the arrow keys.
+                None => self.change(true),
+                Some(0) => self.open(Screen::Controls),
+                Some(1) => self.save(),
+                Some(_) => self.back(),
+            },
+            Screen::Controls => match cursor {
+                0 => self.change(true),
+                _ => match Control::ALL.get(cursor - 1) {
+                    Some(&control) => {
+                        self.rebinding = Some(control);
+                        self.message = None;
+                    }
+                    None => self.back(),
+                },
+            },
+            _ => self.back(),
+        }
+
+        None
+    }
+
+    /// Changes the selected setting with the left and right keys.
+    fn change(&mut self, up: bool) {
+        match self.screen {
+            Screen::Settings => {
+                if let Some(setting) = Setting::ALL.get(self.cursor) {
+                    setting.change(&mut self.config, up);
+                    self.message = None;
+                }
+            }
+            Screen::Controls if self.cursor == 0 => {
+                self.config.controls.preset = match self.config.controls.preset {
+                    ControlsPreset::Classic => ControlsPreset::Guideline,
+                    ControlsPreset::Guideline => ControlsPreset::Classic,
+                };
+                self.message = None;
+            }
+            Screen::HighScores => {
+                let index = GameMode::ALL
+                    .iter()
+                    .position(|&mode| mode == self.high_score_mode)
+                    .unwrap_or(0);
+                let count = GameMode::ALL.len();
+                let next = if up { index + 1 } else { index + count - 1 };
+
+                self.high_score_mode = GameMode::ALL[next % count];
+            }
+            _ => (),
+        }
+    }
+
+    /// Binds the pressed key to the control, instead of all of its other keys. Escape keeps the old keys.
+    fn bind(&mut self, control: Control, keycode: KeyCode) {
+        if keycode == KeyCode::Escape {
+            return;
+        }
+
+        let name = format!("{:?}", keycode);
+
+        // The names of the key codes are the names in the config, but not every key can be read back.
+        if key_from_name(&name) != Some(keycode) {
+            self.message = Some("THIS KEY CAN NOT BE USED.".to_string());
+            return;
+        }
+
+        *self.config.controls.custom_keys_mut(control) = Some(vec![name]);
+    }
+
+    /// Gives the selected control the keys of the preset back, with Backspace.
+    fn reset_control(&mut self) {
+        if self.screen != Screen::Controls || self.cursor == 0 {
+            return;
+        }
+
+        if let Some(&control) = Control::ALL.get(self.cursor - 1) {
+            *self.config.controls.custom_keys_mut(control) = None;
+            self.message = None;
+        }
+    }
+
+    /// Writes the settings of the settings menu into the config file.
+    fn save(&mut self) {
+        if let Some(conflict) = self.config.controls.conflicts().first() {
+            println!("{}", conflict);
+            self.message = Some("A KEY IS BOUND TWICE.".to_string());
+            return;
+        }
+
+        self.message = Some(match save_settings(&self.config, &Setting::FIELDS) {
+            Ok(path) => {
+                println!("Saved the settings to {}", path.display());
+                "SAVED!".to_string()
+            }
+            Err(error) => {
+                println!("Could not save the settings: {}", error);
+                "COULD NOT SAVE.".to_string()
+            }
+        });
+    }
+
+    /// Draws the current menu screen.
+    pub fn draw(
+        &self,
+        ctx: &mut ggez::Context,
+        font: graphics::Font,
+        high_scores: &HighScores,
+    ) -> GameResult {
+        match self.screen {
+            Screen::Title => {
+                draw_title(ctx, font, "TETRIS!")?;
+                self.draw_entries(ctx, font, &TITLE_ENTRIES.map(String::from), 20.0)?;
+            }
+            Screen::ModeSelect => {
+                draw_title(ctx, font, "MODE")?;
+
+                let mut entries: Vec<String> = GameMode::ALL
+                    .iter()
+                    .map(|mode| mode.name().to_string())
+                    .collect();
+                entries.push("BACK".to_string());
+
+                self.draw_entries(ctx, font, &entries, 20.0)?;
+
+                if let Some(mode) = GameMode::ALL.get(self.cursor) {
+                    draw_text(ctx, font, mode.description(), 12.0, [40.0, 500.0])?;
+                }
+            }
+            Screen::Settings => {
+                draw_title(ctx, font, "SETTINGS")?;
+
+                let mut entries: Vec<String> = Setting::ALL
+                    .iter()
+                    .map(|setting| format!("{}: {}", setting.name(), setting.value(&self.config)))
+                    .collect();
+                entries.extend(["CONTROLS", "SAVE", "BACK"].map(String::from));
+
+                self.draw_entries(ctx, font, &entries, 16.0)?;
+                self.draw_message(ctx, font)?;
+            }
+            Screen::Controls => {
+                draw_title(ctx, font, "CONTROLS")?;
+
+                let preset = match self.config.controls.preset {
+                    ControlsPreset::Classic => "CLASSIC",
+                    ControlsPreset::Guideline => "GUIDELINE",
+                };
+
+                let mut entries = vec![format!("PRESET: {}", preset)];
+
+                entries.extend(Control::ALL.iter().map(|&control| {
+                    let keys = if self.rebinding == Some(control) {
+                        "PRESS A KEY...".to_string()
+                    } else {
+                        self.config.controls.keys(control).join(", ")
+                    };
+
+                    format!(
+                        "{}:\n  {}",
+                        control.name().replace('_', " "),
+                        keys.to_uppercase()
+                    )
+                }));
+                entries.push("BACK".to_string());
+
+                self.draw_entries(ctx, font, &entries, 12.0)?;
+
+                draw_text(
+                    ctx,
+                    font,
+                    "ENTER: NEW KEY\nBACKSPACE: KEYS OF THE PRESET",
+                    10.0,
+                    [40.0, 900.0],
+                )?;
+
+                if !self.config.controls.conflicts().is_empty() {
+                    draw_text(ctx, font, "A KEY IS BOUND TWICE.", 14.0, [40.0, 930.0])?;
+                }
+
+                self.draw_message(ctx, font)?;
+            }
+            Screen::HighScores => {
+                draw_title(ctx, font, "HIGH SCORES")?;
+
+                let mut config = self.config.clone();
+                config.mode = self.high_score_mode;
+                let ruleset = ruleset(&config);
+
+                draw_text(
+                    ctx,
+                    font,
+                    &format!(
+                        "< {} >\n\n{}",
+                        self.high_score_mode.name(),
+                        ruleset.to_uppercase()
+                    ),
+                    12.0,
+                    [40.0, 150.0],
+                )?;
+
+                let table = high_scores.table(&ruleset);
+
+                if table.is_empty() {
+                    draw_text(ctx, font, "NO HIGH SCORES YET.", 14.0, [40.0, 230.0])?;
+                }
+
+                draw_high_score_table(ctx, font, table, self.high_score_mode, None, [40.0, 230.0])?;
+            }
+            Screen::Credits => {
+                draw_title(ctx, font, "CREDITS")?;
+                draw_text(ctx, font, CREDITS, 16.0, [40.0, 150.0])?;
+            }
+            Screen::Playing => (),
+        }
+
+        Ok(())
+    }
+
+    /// Draws the entries of a menu below each other, the selected one highlighted.
+    fn draw_entries(
+        &self,
+        ctx: &mut ggez::Context,
+        font: graphics::Font,
+        entries: &[String],
+        scale: f32,
+    ) -> GameResult {
+        let mut y = 150.0;
+
+        for (i, entry) in entries.iter().enumerate() {
+            let (prefix, color) = if i == self.cursor {
+                ("> ", HIGHLIGHT_COLOR)
+            } else {
+                ("  ", Color::WHITE)
+            };
+
+            let text = Text::new(
+                TextFragment::new(format!("{}{}", prefix, entry))
+                    .font(font)
+                    .scale(scale)
+                    .color(color),
+            );
+
+            graphics::draw(ctx, &text, graphics::DrawParam::default().dest([40.0, y]))?;
+
+            y += (entry.lines().count() as f32).mul_add(scale * 1.5, scale);
+        }
+
+        Ok(())
+    }
+
+    fn draw_message(&self, ctx: &mut ggez::Context, font: graphics::Font) -> GameResult {
+        match &self.message {
+            Some(message) => draw_text(ctx, font, message, 14.0, [40.0, 950.0]),
+            None => Ok(()),
+        }
+    }
+}
+
+fn draw_title(ctx: &mut ggez::Context, font: graphics::Font, title: &str) -> GameResult {
+    draw_text(ctx, font, title, 32.0, [40.0, 60.0])
+}
+
+fn draw_text(
+    ctx: &mut ggez::Context,
+    font: graphics::Font,
+    text: &str,
+    scale: f32,
+    dest: [f32; 2],
+) -> GameResult {
+    let text = Text::new(TextFragment::new(text).font(font).scale(scale));
+
+    graphics::draw(ctx, &text, graphics::DrawParam::default().dest(dest))
+}
+
+/// Draws a high score table, 2 lines for every game.
+/// Sprint games show their time in place of the score, since that is what they are ranked by.
+pub fn draw_high_score_table(
+    ctx: &mut ggez::Context,
+    font: graphics::Font,
+    table: &[HighScore],
+    mode: GameMode,
+    highlight: Option<usize>,
+    dest: [f32; 2],
+) -> GameResult {
+    for (place, high_score) in table.iter().enumerate() {
+        let color = if highlight == Some(place) {
+            HIGHLIGHT_COLOR
+        } else {
+            Color::WHITE
+        };
+
+        let result = if mode.ranks_by_time() {
+            high_score.time()
+        } else {
+            high_score.score.separate_with_commas()
+        };
+
+        let score_text = Text::new(
+            TextFragment::new(format!(
+                "{:>2}. {:<10} {:>13}",
+                place + 1,
+                high_score.name,
+                result
+            ))
+            .font(font)
+            .scale(14.0)
+            .color(color),
+        );
+
+        let details_text = Text::new(
+            TextFragment::new(format!(
+                "LV {}  LINES {}  PIECES {}  {}  {}",
+                high_score.level.separate_with_commas(),
+                high_score.lines.separate_with_commas(),
+                high_score.total_pieces().separate_with_commas(),
+                high_score.time(),
+                high_score.day()
+            ))
+            .font(font)
+            .scale(10.0)
+            .color(color),
+        );
+
+        let y = (place as f32).mul_add(36.0, dest[1]);
+
+        graphics::draw(
+            ctx,
+            &score_text,
+            graphics::DrawParam::default().dest([dest[0], y]),
+        )?;
+        graphics::draw(
+            ctx,
+            &details_text,
+            graphics::DrawParam::default().dest([dest[0] + 56.0, y + 17.0]),
+        )?;
+    }
+
+    Ok(())
+}
@@ -1,4 +1,22 @@
+pub mod animation;
 pub mod board;
+pub mod clear;
 pub mod config;
-pub mod game;
+pub mod controls;
+pub mod engine;
+#[cfg(feature = "gui")]
+pub mod frontend;
+pub mod gravity;
+pub mod highscores;
+pub mod input;
+#[cfg(feature = "gui")]
+pub mod keys;
+#[cfg(feature = "gui")]
+pub mod menu;
+pub mod mode;
 pub mod pieces;
+pub mod randomizer;
+pub mod replay;
+pub mod rotation;
+pub mod scoring;
+pub mod settings;
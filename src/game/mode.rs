@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use super::engine::{Engine, FRAMES_PER_SECOND};
+
+/// How many lines you have to clear in Sprint.
+pub const SPRINT_LINES: u128 = 40;
+
+/// How long a game of Ultra lasts, in frames.
+pub const ULTRA_FRAMES: u64 = 2 * 60 * FRAMES_PER_SECOND as u64;
+
+/// What you play for, picked in the mode select menu.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    // The classic endless game, it only ends when you top out.
+    #[default]
+    Marathon,
+    // Clear 40 lines as fast as you can.
+    Sprint,
+    // Score as many points as you can in 2 minutes.
+    Ultra,
+}
+
+impl GameMode {
+    pub const ALL: [Self; 3] = [Self::Marathon, Self::Sprint, Self::Ultra];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Marathon => "MARATHON",
+            Self::Sprint => "SPRINT",
+            Self::Ultra => "ULTRA",
+        }
+    }
+
+    /// What the goal of the mode is, for the mode select menu.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Marathon => "PLAY UNTIL YOU TOP OUT.",
+            Self::Sprint => "CLEAR 40 LINES AS FAST AS YOU CAN.",
+            Self::Ultra => "SCORE AS MUCH AS YOU CAN IN 2 MINUTES.",
+        }
+    }
+
+    /// Checks if the game reached the goal of the mode, which ends it.
+    pub fn goal_reached(self, engine: &Engine) -> bool {
+        match self {
+            Self::Marathon => false,
+            Self::Sprint => engine.lines_cleared >= SPRINT_LINES,
+            Self::Ultra => engine.frame >= ULTRA_FRAMES,
+        }
+    }
+
+    /// Sprint games are ranked by the fastest time instead of the highest score,
+    /// and only count if all of the lines were cleared.
+    pub fn ranks_by_time(self) -> bool {
+        self == Self::Sprint
+    }
+}
@@ -1,11 +1,9 @@
 use std::fmt;
 
-use rand::prelude::SliceRandom;
-use rand::thread_rng;
-
-use super::board::Board;
-use super::config::Config;
-use super::game::MainGame;
+use super::board::{Board, DEFAULT_WIDTH};
+use super::engine::Engine;
+use super::engine::TopOut;
+use super::rotation::RotationSystem;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PieceType {
@@ -17,13 +15,56 @@ pub enum PieceType {
     O,
     T,
 }
+
+impl PieceType {
+    // Every piece type, in the order they are shown in the piece counts.
+    pub const ALL: [Self; 7] = [
+        Self::I,
+        Self::L,
+        Self::J,
+        Self::S,
+        Self::Z,
+        Self::O,
+        Self::T,
+    ];
+
+    /// The index of the piece type in `PieceType::ALL`, and in the piece counts.
+    pub fn index(self) -> usize {
+        match self {
+            Self::I => 0,
+            Self::L => 1,
+            Self::J => 2,
+            Self::S => 3,
+            Self::Z => 4,
+            Self::O => 5,
+            Self::T => 6,
+        }
+    }
+
+    pub fn color(self) -> (u8, u8, u8) {
+        match self {
+            Self::I => (0, 255, 255),
+            Self::L => (255, 127, 0),
+            Self::J => (0, 0, 255),
+            Self::S => (0, 255, 0),
+            Self::Z => (255, 0, 0),
+            Self::O => (255, 255, 0),
+            Self::T => (128, 0, 128),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Piece {
     pub piece_type: PieceType,
     pub color: (u8, u8, u8),
-    pub offset: (usize, usize),
+    // The position of the top left corner of the piece's bounding box.
+    // This can be off the board, as long as the blocks themselves are not.
+    pub offset: (isize, isize),
     pub rotations: usize,
-    pub orientations: Vec<Vec<(usize, usize)>>,
+    pub orientations: [[(usize, usize); 4]; 4],
+    // The (row, column) kick the last successful rotation used, for the T-Spin detection.
+    pub last_kick: Option<(isize, isize)>,
 }
 
 impl fmt::Display for Piece {
@@ -33,270 +74,126 @@ impl fmt::Display for Piece {
 }
 
 impl Piece {
-    /// We get a new random piece depending on the piece RNG selected.
-    pub fn get_random_piece(piece_bag: &mut Vec<Self>, config: Config, first_bag: bool) -> Self {
-        if !config.modern_piece_rng {
-            return Self::get_random_piece_classic();
-        }
-
-        Self::get_random_piece_modern(piece_bag, config, first_bag)
-    }
-
-    /// Gets a completely random piece, the "oldschool" Tetris Piece Algorithm.
-    pub fn get_random_piece_classic() -> Self {
-        let random_pieces = [
-            Self::get_i_piece(),
-            Self::get_l_piece(),
-            Self::get_j_piece(),
-            Self::get_s_piece(),
-            Self::get_z_piece(),
-            Self::get_o_piece(),
-            Self::get_t_piece(),
-        ];
-
-        random_pieces
-            .choose(&mut thread_rng())
-            .unwrap_or(&Self::get_i_piece())
-            .clone()
-    }
-
-    /// Gets a shuffled sequence of 7 Pieces, the modern Tetris Piece Algorithm.
-    pub fn get_random_piece_modern(
-        piece_bag: &mut Vec<Self>,
-        config: Config,
-        first_bag: bool,
-    ) -> Self {
-        if piece_bag.is_empty() {
-            *piece_bag = Self::get_new_piece_bag(config, first_bag);
-        }
+    /// Gets a fresh piece of the given type in its spawn position,
+    /// looking like the rotation system says it should.
+    /// On boards that are not 10 columns wide, the piece is moved so it stays in the middle.
+    /// The piece spawns in the lowest two rows of the hidden buffer, rows 21 and 22 counted from the bottom.
+    pub fn new(piece_type: PieceType, rotation_system: &dyn RotationSystem, board: &Board) -> Self {
+        let (row, column) = rotation_system.spawn_offset(piece_type);
+        let row = row + board.buffer as isize - 2;
+        let column = column + (board.width as isize - DEFAULT_WIDTH as isize) / 2;
 
-        piece_bag.pop().unwrap()
-    }
-
-    // These are its own functions because we need the individual pieces elsewhere too.
-    pub fn get_i_piece() -> Self {
-        Self {
-            piece_type: PieceType::I,
-            color: (0, 255, 255),
-            offset: (0, 3),
-            rotations: 0,
-            orientations: vec![
-                vec![(0, 0), (0, 1), (0, 2), (0, 3)],
-                vec![(0, 0), (1, 0), (2, 0), (3, 0)],
-                vec![(0, 0), (0, 1), (0, 2), (0, 3)],
-                vec![(0, 0), (1, 0), (2, 0), (3, 0)],
-            ],
-        }
-    }
-
-    pub fn get_l_piece() -> Self {
         Self {
-            piece_type: PieceType::L,
-            color: (255, 127, 0),
-            offset: (0, 3),
+            piece_type,
+            color: piece_type.color(),
+            offset: (row, column),
             rotations: 0,
-            orientations: vec![
-                vec![(1, 1), (1, 0), (1, 2), (0, 2)],
-                vec![(0, 0), (1, 0), (2, 0), (2, 1)],
-                vec![(0, 0), (0, 1), (0, 2), (1, 0)],
-                vec![(0, 0), (0, 1), (1, 1), (2, 1)],
-            ],
+            orientations: rotation_system.orientations(piece_type),
+            last_kick: None,
         }
     }
 
-    pub fn get_j_piece() -> Self {
-        Self {
-            piece_type: PieceType::J,
-            color: (0, 0, 255),
-            offset: (0, 3),
-            rotations: 0,
-            orientations: vec![
-                vec![(0, 0), (1, 0), (1, 1), (1, 2)],
-                vec![(0, 0), (0, 1), (1, 0), (2, 0)],
-                vec![(0, 0), (0, 1), (0, 2), (1, 2)],
-                vec![(0, 1), (1, 1), (2, 1), (2, 0)],
-            ],
-        }
+    /// The board coordinates of the blocks of the piece with the given rotation and offset.
+    /// Returns None if a block would end up left of or above the board.
+    pub fn blocks_at(
+        &self,
+        rotation: usize,
+        offset: (isize, isize),
+    ) -> Option<Vec<(usize, usize)>> {
+        self.orientations[rotation]
+            .iter()
+            .map(|block| {
+                Some((
+                    block.0.checked_add_signed(offset.0)?,
+                    block.1.checked_add_signed(offset.1)?,
+                ))
+            })
+            .collect()
     }
 
-    pub fn get_s_piece() -> Self {
-        Self {
-            piece_type: PieceType::S,
-            color: (0, 255, 0),
-            offset: (0, 3),
-            rotations: 0,
-            orientations: vec![
-                vec![(1, 0), (1, 1), (0, 1), (0, 2)],
-                vec![(0, 0), (1, 0), (1, 1), (2, 1)],
-                vec![(1, 0), (1, 1), (0, 1), (0, 2)],
-                vec![(0, 0), (1, 0), (1, 1), (2, 1)],
-            ],
-        }
+    /// The board coordinates of the blocks of the piece in its current position.
+    pub fn blocks(&self) -> Vec<(usize, usize)> {
+        self.orientations[self.rotations]
+            .iter()
+            .map(|block| {
+                (
+                    (block.0 as isize + self.offset.0) as usize,
+                    (block.1 as isize + self.offset.1) as usize,
+                )
+            })
+            .collect()
     }
 
-    pub fn get_z_piece() -> Self {
-        Self {
-            piece_type: PieceType::Z,
-            color: (255, 0, 0),
-            offset: (0, 3),
-            rotations: 0,
-            orientations: vec![
-                vec![(0, 0), (0, 1), (1, 1), (1, 2)],
-                vec![(1, 0), (2, 0), (0, 1), (1, 1)],
-                vec![(0, 0), (0, 1), (1, 1), (1, 2)],
-                vec![(1, 0), (2, 0), (0, 1), (1, 1)],
-            ],
+    /// Moves the piece into the new rotation and position, if there is room for it.
+    /// Returns a bool whether or not the move succeeded.
+    fn try_move(piece: &mut Self, board: &Board, rotation: usize, shift: (isize, isize)) -> bool {
+        let offset = (piece.offset.0 + shift.0, piece.offset.1 + shift.1);
+
+        match piece.blocks_at(rotation, offset) {
+            Some(b) if !board.collides(&b) => {
+                piece.rotations = rotation;
+                piece.offset = offset;
+                true
+            }
+            _ => false,
         }
     }
 
-    pub fn get_o_piece() -> Self {
-        Self {
-            piece_type: PieceType::O,
-            color: (255, 255, 0),
-            offset: (0, 3),
-            rotations: 0,
-            orientations: vec![
-                vec![(0, 0), (0, 1), (1, 0), (1, 1)],
-                vec![(0, 0), (0, 1), (1, 0), (1, 1)],
-                vec![(0, 0), (0, 1), (1, 0), (1, 1)],
-                vec![(0, 0), (0, 1), (1, 0), (1, 1)],
-            ],
+    /// Spawning a new piece on the board, the piece has to be the current piece of the game already.
+    /// If a rotation is held down, the piece spawns in that rotation if there is room for it.
+    pub fn spawn_piece(piece: Self, game: &mut Engine, spawn_held: bool) {
+        if game.game_over {
+            return;
         }
-    }
 
-    pub fn get_t_piece() -> Self {
-        Self {
-            piece_type: PieceType::T,
-            color: (128, 0, 128),
-            offset: (0, 3),
-            rotations: 0,
-            orientations: vec![
-                vec![(1, 0), (1, 1), (0, 1), (1, 2)],
-                vec![(0, 0), (1, 0), (2, 0), (1, 1)],
-                vec![(0, 0), (0, 1), (0, 2), (1, 1)],
-                vec![(1, 0), (0, 1), (1, 1), (2, 1)],
-            ],
+        if !spawn_held {
+            game.piece_count[piece.piece_type.index()] += 1;
         }
-    }
-
-    /// Gets a new "bag" of pieces, each Piece X times, shuffled.
-    pub fn get_new_piece_bag(config: Config, first_bag: bool) -> Vec<Self> {
-        let mut random_pieces: Vec<Self> = Vec::new();
-        let mut bags = config.bag_amount;
 
-        if bags < 1 {
-            bags = 1;
-        }
+        game.reset_lock_delay();
 
-        for _ in 0..bags {
-            random_pieces.push(Self::get_i_piece());
-            random_pieces.push(Self::get_l_piece());
-            random_pieces.push(Self::get_j_piece());
-            random_pieces.push(Self::get_s_piece());
-            random_pieces.push(Self::get_z_piece());
-            random_pieces.push(Self::get_o_piece());
-            random_pieces.push(Self::get_t_piece());
-        }
+        // The Initial Rotation System never kicks, the piece just spawns unrotated if the rotation does not fit.
+        if let Some(rotation) = game.initial_rotation() {
+            let current = &game.current_piece;
 
-        random_pieces.shuffle(&mut thread_rng());
+            let fits = current
+                .blocks_at(rotation, current.offset)
+                .is_some_and(|blocks| !game.board.collides(&blocks));
 
-        // If the first piece no overhang setting is set to true,
-        // we will prevent Z,S & Os spawning as the first piece to, well, prevent overhangs.
-        if first_bag && config.first_piece_no_overhang {
-            while [PieceType::Z, PieceType::S, PieceType::O]
-                .contains(&random_pieces.last().unwrap().piece_type)
-            {
-                random_pieces.shuffle(&mut thread_rng());
+            if fits {
+                game.current_piece.rotations = rotation;
             }
         }
 
-        random_pieces.clone()
-    }
-
-    /// Spawning a new piece on the board.
-    pub fn spawn_piece(piece: Self, game: &mut MainGame, spawn_held: bool) {
-        if game.game_over {
+        // If there is no room to spawn a new piece it is a block out, and the game is over.
+        if game.board.collides(&game.current_piece.blocks()) {
+            game.game_over = true;
+            game.top_out = Some(TopOut::BlockOut);
             return;
         }
 
-        let piece_blocks = piece.orientations[0].clone();
-
-        if !spawn_held {
-            match piece.piece_type {
-                PieceType::I => game.piece_count[0] += 1,
-                PieceType::L => game.piece_count[1] += 1,
-                PieceType::J => game.piece_count[2] += 1,
-                PieceType::S => game.piece_count[3] += 1,
-                PieceType::Z => game.piece_count[4] += 1,
-                PieceType::O => game.piece_count[5] += 1,
-                PieceType::T => game.piece_count[6] += 1,
-            }
-        }
-
-        for block in piece_blocks {
-            let temp_block_0 = block.0 + piece.offset.0;
-            let temp_block_1 = block.1 + piece.offset.1;
-
-            if game.board.board[temp_block_0][temp_block_1] == ' ' {
-                game.board.board[temp_block_0][temp_block_1] = '#';
-                game.board.color[temp_block_0][temp_block_1] = piece.color;
-            } else if !spawn_held {
-                // If there is no room to spawn a new piece we set the game over flag to true.
-                game.game_over = true;
-            }
-        }
+        // Like in the modern games the piece moves down a row right away if it can,
+        // so that it peeks into the visible board from the start.
+        game.step_gravity();
+        game.apply_max_gravity();
     }
 
     /// Holds a piece and spawns the old piece held, if available.
-    pub fn hold_piece(game: &mut MainGame) {
-        if !game.can_swap || !game.config.holding_enabled || game.game_over
-        {
+    pub fn hold_piece(game: &mut Engine) {
+        if !game.can_swap || !game.config.holding_enabled || game.game_over {
             return;
         }
 
         // If no piece is held, we just de-spawn the current piece.
         if game.held_piece.is_none() {
-            for block in game.current_piece.orientations[game.current_piece.rotations].clone() {
-                let temp_block_0 = block.0 + game.current_piece.offset.0;
-                let temp_block_1 = block.1 + game.current_piece.offset.1;
-
-                game.board.board[temp_block_0][temp_block_1] = ' ';
-                game.board.color[temp_block_0][temp_block_1] = (255, 255, 255);
-            }
-
             game.held_piece = Some(game.current_piece.clone());
 
-            Self::spawn_piece(game.next_piece.clone(), game, true);
-
-            let new_piece = Self::get_random_piece(&mut game.piece_bag, game.config.clone(), false);
-            game.current_piece = game.next_piece.clone();
-            Self::spawn_piece(game.next_piece.clone(), game, true);
-            match game.next_piece.piece_type {
-                PieceType::I => game.piece_count[0] += 1,
-                PieceType::L => game.piece_count[1] += 1,
-                PieceType::J => game.piece_count[2] += 1,
-                PieceType::S => game.piece_count[3] += 1,
-                PieceType::Z => game.piece_count[4] += 1,
-                PieceType::O => game.piece_count[5] += 1,
-                PieceType::T => game.piece_count[6] += 1,
-            }
-
-            game.next_piece = new_piece;
+            game.spawn_next_piece();
         // If a piece is held, we de-spawn the current piece and spawn the old held piece.
         } else {
             let copied_piece = game.current_piece.clone();
 
-            for block in game.current_piece.orientations[game.current_piece.rotations].clone() {
-                let temp_block_0 = block.0 + game.current_piece.offset.0;
-                let temp_block_1 = block.1 + game.current_piece.offset.1;
-
-                game.board.board[temp_block_0][temp_block_1] = ' ';
-                game.board.color[temp_block_0][temp_block_1] = (255, 255, 255);
-            }
-
-            let mut held_piece_copy = game.held_piece.clone().unwrap();
-            held_piece_copy.offset = (0, 3);
-            held_piece_copy.rotations = 0;
+            let held_piece_copy = game.new_piece(game.held_piece.clone().unwrap().piece_type);
 
             game.current_piece = held_piece_copy.clone();
             Self::spawn_piece(held_piece_copy, game, true);
@@ -308,181 +205,102 @@ impl Piece {
 
     /// Rotates a piece, either clockwise or counter-clockwise.
     /// Returns a bool whether or not the rotation succeeded.
-    pub fn rotate_piece(piece: &mut Self, board: &mut Board, clockwise: bool) -> bool {
-        let mut temp_rotation: usize;
-
-        if clockwise {
-            temp_rotation = piece.rotations + 1;
-
-            if temp_rotation == 4 {
-                temp_rotation = 0;
-            }
-        } else if piece.rotations == 0 {
-            temp_rotation = 3;
+    pub fn rotate_piece(
+        piece: &mut Self,
+        board: &Board,
+        rotation_system: &dyn RotationSystem,
+        clockwise: bool,
+    ) -> bool {
+        let rotation = if clockwise {
+            (piece.rotations + 1) % 4
         } else {
-            temp_rotation = piece.rotations - 1;
-        }
-
-        // We first check if the rotation is valid, and return if not.
-        for block in piece.orientations[temp_rotation].clone() {
-            let temp_block_0 = block.0 + piece.offset.0;
-            let temp_block_1 = block.1 + piece.offset.1;
-
-            let mut temp_piece_pos: Vec<(usize, usize)> = vec![];
-
-            for block in piece.orientations[piece.rotations].clone() {
-                let temp_block_2 = block.0 + piece.offset.0;
-                let temp_block_3 = block.1 + piece.offset.1;
-                temp_piece_pos.push((temp_block_2, temp_block_3));
-            }
+            (piece.rotations + 3) % 4
+        };
 
-            if temp_block_0 > 19
-                || temp_block_1 > 9
-                || (board.board[temp_block_0][temp_block_1] == '#'
-                    && !temp_piece_pos.contains(&(temp_block_0, temp_block_1)))
-            {
-                return false;
-            }
-        }
-
-        // And then we actually do it.
-        for block in piece.orientations[piece.rotations].clone() {
-            let temp_block_0 = block.0 + piece.offset.0;
-            let temp_block_1 = block.1 + piece.offset.1;
-
-            board.board[temp_block_0][temp_block_1] = ' ';
-            board.color[temp_block_0][temp_block_1] = (255, 255, 255);
-        }
-
-        if clockwise {
-            piece.rotations += 1;
+        Self::kick_piece(piece, board, rotation_system, rotation)
+    }
 
-            if piece.rotations == 4 {
-                piece.rotations = 0;
-            }
-        } else if piece.rotations == 0 {
-            piece.rotations = 3;
-        } else {
-            piece.rotations -= 1;
-        }
+    /// Rotates a piece by 180°.
+    /// Returns a bool whether or not the rotation succeeded.
+    pub fn rotate_piece_180(
+        piece: &mut Self,
+        board: &Board,
+        rotation_system: &dyn RotationSystem,
+    ) -> bool {
+        let rotation = (piece.rotations + 2) % 4;
+
+        Self::kick_piece(piece, board, rotation_system, rotation)
+    }
 
-        for block in piece.orientations[piece.rotations].clone() {
-            let temp_block_0 = block.0 + piece.offset.0;
-            let temp_block_1 = block.1 + piece.offset.1;
+    /// Tries to rotate a piece into the new rotation,
+    /// trying every kick of the rotation system until one fits.
+    fn kick_piece(
+        piece: &mut Self,
+        board: &Board,
+        rotation_system: &dyn RotationSystem,
+        rotation: usize,
+    ) -> bool {
+        let kick = rotation_system
+            .kicks(piece, board, rotation)
+            .into_iter()
+            .find(|&kick| Self::try_move(piece, board, rotation, kick));
 
-            board.board[temp_block_0][temp_block_1] = '#';
-            board.color[temp_block_0][temp_block_1] = piece.color;
+        if kick.is_some() {
+            piece.last_kick = kick;
         }
 
-        true
+        kick.is_some()
     }
 
     /// Moves a piece down a row.
     /// Returns a bool whether or not the move succeeded.
-    pub fn move_piece_down(game: &mut MainGame, shadow: bool, ctx: &mut ggez::Context) -> bool {
-        if game.game_over {
-            return false;
-        }
-
-        for block in game.current_piece.orientations[game.current_piece.rotations].clone() {
-            let temp_block_0 = block.0 + game.current_piece.offset.0;
-            let temp_block_1 = block.1 + game.current_piece.offset.1;
-
-            let mut temp_piece_pos: Vec<(usize, usize)> = vec![];
-
-            for block in game.current_piece.orientations[game.current_piece.rotations].clone() {
-                let temp_block_2 = block.0 + game.current_piece.offset.0;
-                let temp_block_3 = block.1 + game.current_piece.offset.1;
-                temp_piece_pos.push((temp_block_2, temp_block_3));
-            }
-
-            if temp_block_0 == 19
-                || (game.board.board[temp_block_0 + 1][temp_block_1] == '#'
-                    && !temp_piece_pos.contains(&(temp_block_0 + 1, temp_block_1)))
-            {
-                if !shadow {
-                    // If the piece cannot go any further and it is not a "shadow" piece
-                    // we increase the score, level, check for full lines and so on.
-                    let lines_erased = u128::from(MainGame::erase_lines(
-                        ctx,
-                        &mut game.board,
-                        &mut game.score,
-                        game.level,
-                        &mut game.clear_count,
-                    ));
-
-                    game.lines_cleared += lines_erased;
-
-                    game.level = ((game.lines_cleared as f64 + 1.0) / 10.0).ceil() as u128;
-
-                    let new_piece =
-                        Self::get_random_piece(&mut game.piece_bag, game.config.clone(), false);
-                    game.current_piece = game.next_piece.clone();
-                    Self::spawn_piece(game.next_piece.clone(), game, false);
-                    game.next_piece = new_piece;
-
-                    game.can_swap = true;
-                    game.score += game.down_presses;
-                }
-
-                return false;
-            }
-        }
-
-        for block in game.current_piece.orientations[game.current_piece.rotations].clone() {
-            let temp_block_0 = block.0 + game.current_piece.offset.0;
-            let temp_block_1 = block.1 + game.current_piece.offset.1;
-
-            game.board.board[temp_block_0][temp_block_1] = ' ';
-            game.board.color[temp_block_0][temp_block_1] = (255, 255, 255);
-        }
-        game.current_piece.offset.0 += 1;
+    pub fn move_piece_down(piece: &mut Self, board: &Board) -> bool {
+        let rotation = piece.rotations;
 
-        for block in game.current_piece.orientations[game.current_piece.rotations].clone() {
-            let temp_block_0 = block.0 + game.current_piece.offset.0;
-            let temp_block_1 = block.1 + game.current_piece.offset.1;
+        Self::try_move(piece, board, rotation, (1, 0))
+    }
 
-            game.board.board[temp_block_0][temp_block_1] = '#';
-            game.board.color[temp_block_0][temp_block_1] = game.current_piece.color;
+    /// Checks if the piece is resting on the floor or on another piece.
+    pub fn is_grounded(&self, board: &Board) -> bool {
+        match self.blocks_at(self.rotations, (self.offset.0 + 1, self.offset.1)) {
+            Some(b) => board.collides(&b),
+            None => true,
         }
-
-        true
     }
 
-    /// Drops a piece down as far as it will go.
-    pub fn drop_piece_down(game: &mut MainGame, shadow: bool, ctx: &mut ggez::Context) {
+    /// Drops a piece down as far as it will go and locks it.
+    pub fn drop_piece_down(game: &mut Engine) {
         if game.game_over {
             return;
         }
 
-        let mut drop_counter: u128 = 0;
+        let mut rows: u128 = 0;
 
-        loop {
-            let stop = Self::move_piece_down(game, shadow, ctx);
-            drop_counter += 1;
-            if !stop {
-                break;
-            }
+        while Self::move_piece_down(&mut game.current_piece, &game.board) {
+            rows += 1;
         }
 
-        game.score += drop_counter;
+        game.score += game.scoring.hard_drop_points(rows);
+        game.lock_piece();
     }
 
     /// Gets a "shadow" piece, which is the current piece, if it were dropped as far as it will go in the current position.
     /// This is just for drawing a shaded version of the piece on the board.
-    pub fn get_shadow_piece(game: &mut MainGame, ctx: &mut ggez::Context) -> Vec<(usize, usize)> {
-        let mut shadow_game = game.clone();
+    pub fn get_shadow_piece(game: &Engine) -> Vec<(usize, usize)> {
+        let piece = &game.current_piece;
 
-        Self::drop_piece_down(&mut shadow_game, true, ctx);
+        let mut shadow_piece_coordinates = piece.blocks();
+        let mut drop = 1;
 
-        let mut shadow_piece_coordinates: Vec<(usize, usize)> = Vec::new();
-
-        for block in
-            shadow_game.current_piece.orientations[shadow_game.current_piece.rotations].clone()
+        while let Some(blocks) =
+            piece.blocks_at(piece.rotations, (piece.offset.0 + drop, piece.offset.1))
         {
-            let temp_block_0 = block.0 + shadow_game.current_piece.offset.0;
-            let temp_block_1 = block.1 + shadow_game.current_piece.offset.1;
-            shadow_piece_coordinates.push((temp_block_0, temp_block_1));
+            if game.board.collides(&blocks) {
+                break;
+            }
+
+            shadow_piece_coordinates = blocks;
+            drop += 1;
         }
 
         shadow_piece_coordinates
@@ -490,87 +308,17 @@ impl Piece {
 
     /// Moves a piece left on the board.
     /// Returns a bool whether or not the move succeeded.
-    pub fn move_piece_left(piece: &mut Self, board: &mut Board) -> bool {
-        for block in piece.orientations[piece.rotations].clone() {
-            let temp_block_0 = block.0 + piece.offset.0;
-            let temp_block_1 = block.1 + piece.offset.1;
-
-            let mut temp_piece_pos: Vec<(usize, usize)> = vec![];
-
-            for block in piece.orientations[piece.rotations].clone() {
-                let temp_block_2 = block.0 + piece.offset.0;
-                let temp_block_3 = block.1 + piece.offset.1;
-                temp_piece_pos.push((temp_block_2, temp_block_3));
-            }
-
-            if temp_block_1 == 0
-                || (board.board[temp_block_0][temp_block_1 - 1] == '#'
-                    && !temp_piece_pos.contains(&(temp_block_0, temp_block_1 - 1)))
-            {
-                return false;
-            }
-        }
-
-        for block in piece.orientations[piece.rotations].clone() {
-            let temp_block_0 = block.0 + piece.offset.0;
-            let temp_block_1 = block.1 + piece.offset.1;
-
-            board.board[temp_block_0][temp_block_1] = ' ';
-            board.color[temp_block_0][temp_block_1] = (255, 255, 255);
-        }
-        piece.offset.1 -= 1;
-
-        for block in piece.orientations[piece.rotations].clone() {
-            let temp_block_0 = block.0 + piece.offset.0;
-            let temp_block_1 = block.1 + piece.offset.1;
-
-            board.board[temp_block_0][temp_block_1] = '#';
-            board.color[temp_block_0][temp_block_1] = piece.color;
-        }
+    pub fn move_piece_left(piece: &mut Self, board: &Board) -> bool {
+        let rotation = piece.rotations;
 
-        true
+        Self::try_move(piece, board, rotation, (0, -1))
     }
 
     /// Moves a piece right on the board.
     /// Returns a bool whether or not the move succeeded.
-    pub fn move_piece_right(piece: &mut Self, board: &mut Board) -> bool {
-        for block in piece.orientations[piece.rotations].clone() {
-            let temp_block_0 = block.0 + piece.offset.0;
-            let temp_block_1 = block.1 + piece.offset.1;
-
-            let mut temp_piece_pos: Vec<(usize, usize)> = vec![];
-
-            for block in piece.orientations[piece.rotations].clone() {
-                let temp_block_2 = block.0 + piece.offset.0;
-                let temp_block_3 = block.1 + piece.offset.1;
-                temp_piece_pos.push((temp_block_2, temp_block_3));
-            }
-
-            if temp_block_1 == 9
-                || (board.board[temp_block_0][temp_block_1 + 1] == '#'
-                    && !temp_piece_pos.contains(&(temp_block_0, temp_block_1 + 1)))
-            {
-                return false;
-            }
-        }
-
-        for block in piece.orientations[piece.rotations].clone() {
-            let temp_block_0 = block.0 + piece.offset.0;
-            let temp_block_1 = block.1 + piece.offset.1;
-
-            board.board[temp_block_0][temp_block_1] = ' ';
-            board.color[temp_block_0][temp_block_1] = (255, 255, 255);
-        }
-        piece.offset.1 += 1;
-
-        for block in piece.orientations[piece.rotations].clone() {
-            let temp_block_0 = block.0 + piece.offset.0;
-            let temp_block_1 = block.1 + piece.offset.1;
-
-            board.board[temp_block_0][temp_block_1] = '#';
-            board.color[temp_block_0][temp_block_1] = piece.color;
-        }
+    pub fn move_piece_right(piece: &mut Self, board: &Board) -> bool {
+        let rotation = piece.rotations;
 
-        true
+        Self::try_move(piece, board, rotation, (0, 1))
     }
 }
@@ -0,0 +1,541 @@
+use std::collections::VecDeque;
+
+use rand::prelude::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::config::Config;
+use super::engine::GameRng;
+use super::pieces::PieceType;
+
+// The pieces that leave an overhang if they are the very first piece on an empty board.
+const OVERHANG_PIECES: [PieceType; 3] = [PieceType::S, PieceType::Z, PieceType::O];
+
+// Every piece that does not leave an overhang.
+const NO_OVERHANG_PIECES: [PieceType; 4] = [PieceType::I, PieceType::L, PieceType::J, PieceType::T];
+
+/// A randomizer decides which pieces you get, and in which order.
+/// All of the randomness has to come from the given RNG, so a game can be repeated with the same seed.
+pub trait Randomizer: Send {
+    /// Draws the next piece.
+    fn next(&mut self, rng: &mut GameRng) -> PieceType;
+
+    /// A copy of the randomizer with the same state, so the engine can be cloned.
+    fn clone_box(&self) -> Box<dyn Randomizer>;
+}
+
+impl Clone for Box<dyn Randomizer> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// The built-in randomizers, selectable in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RandomizerKind {
+    // Bags of `bag_amount` times every piece.
+    #[serde(rename = "bag")]
+    Bag,
+    #[serde(rename = "7-bag")]
+    Bag7,
+    #[serde(rename = "14-bag")]
+    Bag14,
+    #[serde(rename = "nes")]
+    Nes,
+    #[serde(rename = "tgm1")]
+    Tgm1,
+    #[serde(rename = "tgm3")]
+    Tgm3,
+    #[serde(rename = "random")]
+    Random,
+    // Random, but with the chances of `piece_weights`.
+    #[serde(rename = "weighted")]
+    Weighted,
+}
+
+impl RandomizerKind {
+    pub const ALL: [Self; 8] = [
+        Self::Bag,
+        Self::Bag7,
+        Self::Bag14,
+        Self::Nes,
+        Self::Tgm1,
+        Self::Tgm3,
+        Self::Random,
+        Self::Weighted,
+    ];
+
+    pub fn get(self, config: &Config) -> Box<dyn Randomizer> {
+        let no_overhang = config.first_piece_no_overhang;
+
+        match self {
+            Self::Bag => Box::new(Bag::new(config.bag_amount.max(1) as usize, no_overhang)),
+            Self::Bag7 => Box::new(Bag::new(1, no_overhang)),
+            Self::Bag14 => Box::new(Bag::new(2, no_overhang)),
+            Self::Nes => Box::new(Nes::new(no_overhang)),
+            Self::Tgm1 => Box::new(Tgm1::new()),
+            Self::Tgm3 => Box::new(Tgm3::new()),
+            Self::Random => Box::new(Random::new(no_overhang)),
+            Self::Weighted => Box::new(Weighted::new(config.piece_weights.clone(), no_overhang)),
+        }
+    }
+}
+
+/// How likely every piece is with the weighted randomizer.
+/// A piece with a weight of 2 comes up twice as often as one with a weight of 1, a weight of 0 means never.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PieceWeights {
+    #[serde(rename = "I", default = "weight_default")]
+    pub i: u32,
+    #[serde(rename = "L", default = "weight_default")]
+    pub l: u32,
+    #[serde(rename = "J", default = "weight_default")]
+    pub j: u32,
+    #[serde(rename = "S", default = "weight_default")]
+    pub s: u32,
+    #[serde(rename = "Z", default = "weight_default")]
+    pub z: u32,
+    #[serde(rename = "O", default = "weight_default")]
+    pub o: u32,
+    #[serde(rename = "T", default = "weight_default")]
+    pub t: u32,
+}
+
+fn weight_default() -> u32 {
+    1
+}
+
+impl PieceWeights {
+    pub fn get(&self, piece_type: PieceType) -> u32 {
+        match piece_type {
+            PieceType::I => self.i,
+            PieceType::L => self.l,
+            PieceType::J => self.j,
+            PieceType::S => self.s,
+            PieceType::Z => self.z,
+            PieceType::O => self.o,
+            PieceType::T => self.t,
+        }
+    }
+}
+
+impl Default for PieceWeights {
+    fn default() -> Self {
+        Self {
+            i: 1,
+            l: 1,
+            j: 1,
+            s: 1,
+            z: 1,
+            o: 1,
+            t: 1,
+        }
+    }
+}
+
+/// Shuffles every piece a few times into a "bag" and deals them out, the modern Tetris Piece Algorithm.
+/// With one of every piece you cannot go more than 12 pieces without seeing a specific piece.
+#[derive(Clone)]
+pub struct Bag {
+    bags: usize,
+    bag: Vec<PieceType>,
+    // Only the very first piece of the game is protected from overhangs.
+    first_bag: bool,
+    no_overhang: bool,
+}
+
+impl Bag {
+    pub fn new(bags: usize, no_overhang: bool) -> Self {
+        Self {
+            bags,
+            bag: Vec::new(),
+            first_bag: true,
+            no_overhang,
+        }
+    }
+
+    fn refill(&mut self, rng: &mut GameRng) {
+        for _ in 0..self.bags {
+            self.bag.extend(PieceType::ALL);
+        }
+
+        self.bag.shuffle(rng);
+
+        // Instead of reshuffling until we get lucky, we swap a random one of the other pieces to the front,
+        // so the bag still has every piece and this always takes the same amount of random numbers.
+        if self.first_bag && self.no_overhang {
+            let no_overhang: Vec<usize> = (0..self.bag.len())
+                .filter(|&i| !OVERHANG_PIECES.contains(&self.bag[i]))
+                .collect();
+
+            let first = self.bag.len() - 1;
+            let swap = no_overhang[rng.gen_range(0..no_overhang.len())];
+
+            self.bag.swap(first, swap);
+        }
+
+        self.first_bag = false;
+    }
+}
+
+impl Randomizer for Bag {
+    fn next(&mut self, rng: &mut GameRng) -> PieceType {
+        if self.bag.is_empty() {
+            self.refill(rng);
+        }
+
+        self.bag.pop().unwrap()
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+/// NES Tetris rolls a number from 0 to 7, where 7 and the previous piece mean it rolls again once, from 0 to 6.
+/// The second roll is taken no matter what, so repeats are rare but possible.
+#[derive(Clone)]
+pub struct Nes {
+    previous: Option<PieceType>,
+    no_overhang: bool,
+}
+
+impl Nes {
+    pub fn new(no_overhang: bool) -> Self {
+        Self {
+            previous: None,
+            no_overhang,
+        }
+    }
+}
+
+impl Randomizer for Nes {
+    fn next(&mut self, rng: &mut GameRng) -> PieceType {
+        let piece = if self.previous.is_none() && self.no_overhang {
+            *NO_OVERHANG_PIECES.choose(rng).unwrap()
+        } else {
+            let roll = rng.gen_range(0..8);
+
+            if roll == 7 || Some(PieceType::ALL[roll]) == self.previous {
+                PieceType::ALL[rng.gen_range(0..7)]
+            } else {
+                PieceType::ALL[roll]
+            }
+        };
+
+        self.previous = Some(piece);
+
+        piece
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+/// Tetris The Grand Master remembers the last 4 pieces, and rolls up to 4 times for a piece that is not one of them.
+/// The history starts out as Z, Z, Z, Z and the first piece is never S, Z or O.
+#[derive(Clone)]
+pub struct Tgm1 {
+    history: VecDeque<PieceType>,
+    first: bool,
+}
+
+impl Tgm1 {
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::from([PieceType::Z; 4]),
+            first: true,
+        }
+    }
+}
+
+impl Default for Tgm1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Randomizer for Tgm1 {
+    fn next(&mut self, rng: &mut GameRng) -> PieceType {
+        let mut piece = *NO_OVERHANG_PIECES.choose(rng).unwrap();
+
+        if !self.first {
+            for _ in 0..4 {
+                piece = *PieceType::ALL.choose(rng).unwrap();
+
+                if !self.history.contains(&piece) {
+                    break;
+                }
+            }
+        }
+
+        self.first = false;
+        self.history.pop_front();
+        self.history.push_back(piece);
+
+        piece
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+/// Tetris The Grand Master 3 also remembers the last 4 pieces, but rolls up to 6 times from a pool of 35 pieces.
+/// Every piece that is dealt is replaced in the pool by the piece you have not seen for the longest time,
+/// so long droughts fix themselves. The history starts out as S, Z, S, Z and the first piece is never S, Z or O.
+#[derive(Clone)]
+pub struct Tgm3 {
+    pool: Vec<PieceType>,
+    history: VecDeque<PieceType>,
+    // The pieces in the order they were last seen, the first one is the one you have waited on the longest.
+    drought_order: Vec<PieceType>,
+    first: bool,
+}
+
+impl Tgm3 {
+    pub fn new() -> Self {
+        Self {
+            pool: PieceType::ALL.repeat(5),
+            history: VecDeque::from([PieceType::S, PieceType::Z, PieceType::S, PieceType::Z]),
+            drought_order: vec![
+                PieceType::J,
+                PieceType::I,
+                PieceType::Z,
+                PieceType::L,
+                PieceType::O,
+                PieceType::T,
+                PieceType::S,
+            ],
+            first: true,
+        }
+    }
+}
+
+impl Default for Tgm3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Randomizer for Tgm3 {
+    fn next(&mut self, rng: &mut GameRng) -> PieceType {
+        let piece = if self.first {
+            self.first = false;
+
+            *NO_OVERHANG_PIECES.choose(rng).unwrap()
+        } else {
+            let mut index = 0;
+
+            for roll in 0..6 {
+                index = rng.gen_range(0..self.pool.len());
+
+                if !self.history.contains(&self.pool[index]) || roll == 5 {
+                    break;
+                }
+
+                // A piece in the history makes the most droughted piece more likely for the next roll.
+                self.pool[index] = self.drought_order[0];
+            }
+
+            let piece = self.pool[index];
+
+            // The dealt piece is replaced with the most droughted one, after counting this piece as seen.
+            self.drought_order.retain(|&p| p != piece);
+            self.drought_order.push(piece);
+            self.pool[index] = self.drought_order[0];
+
+            piece
+        };
+
+        self.history.pop_front();
+        self.history.push_back(piece);
+
+        piece
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+/// Gets a completely random piece, the "oldschool" Tetris Piece Algorithm.
+#[derive(Clone)]
+pub struct Random {
+    first: bool,
+    no_overhang: bool,
+}
+
+impl Random {
+    pub fn new(no_overhang: bool) -> Self {
+        Self {
+            first: true,
+            no_overhang,
+        }
+    }
+}
+
+impl Randomizer for Random {
+    fn next(&mut self, rng: &mut GameRng) -> PieceType {
+        let pieces: &[PieceType] = if self.first && self.no_overhang {
+            &NO_OVERHANG_PIECES
+        } else {
+            &PieceType::ALL
+        };
+
+        self.first = false;
+
+        *pieces.choose(rng).unwrap()
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+/// Gets a random piece, with the chances set in the config.
+/// If every weight is 0 every piece is equally likely instead.
+#[derive(Clone)]
+pub struct Weighted {
+    weights: PieceWeights,
+    first: bool,
+    no_overhang: bool,
+}
+
+impl Weighted {
+    pub fn new(weights: PieceWeights, no_overhang: bool) -> Self {
+        Self {
+            weights,
+            first: true,
+            no_overhang,
+        }
+    }
+}
+
+impl Randomizer for Weighted {
+    fn next(&mut self, rng: &mut GameRng) -> PieceType {
+        let mut pieces: &[PieceType] = &PieceType::ALL;
+
+        // If you only gave weight to the S, Z and O pieces, you get one of them first anyways.
+        if self.first
+            && self.no_overhang
+            && NO_OVERHANG_PIECES.iter().any(|&p| self.weights.get(p) > 0)
+        {
+            pieces = &NO_OVERHANG_PIECES;
+        }
+
+        self.first = false;
+
+        // We add up the weights ourselves as u64, rand would add them up as u32 and overflow with big weights.
+        let total: u64 = pieces.iter().map(|&p| u64::from(self.weights.get(p))).sum();
+
+        if total == 0 {
+            return *pieces.choose(rng).unwrap();
+        }
+
+        let mut roll = rng.gen_range(0..total);
+
+        for &piece in pieces {
+            let weight = u64::from(self.weights.get(piece));
+
+            if roll < weight {
+                return piece;
+            }
+
+            roll -= weight;
+        }
+
+        unreachable!("the roll is always below the total weight")
+    }
+
+    fn clone_box(&self) -> Box<dyn Randomizer> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn weighted_handles_the_biggest_weights() {
+        let weights = PieceWeights {
+            i: u32::MAX,
+            l: u32::MAX,
+            j: u32::MAX,
+            s: 0,
+            z: 0,
+            o: 0,
+            t: 0,
+        };
+        let mut randomizer = Weighted::new(weights, true);
+        let mut rng = GameRng::seed_from_u64(1);
+
+        for _ in 0..100 {
+            let piece = randomizer.next(&mut rng);
+
+            assert!([PieceType::I, PieceType::L, PieceType::J].contains(&piece));
+        }
+    }
+
+    fn pieces(kind: RandomizerKind, seed: u64, amount: usize) -> Vec<PieceType> {
+        let config = Config::default();
+        let mut randomizer = kind.get(&config);
+        let mut rng = GameRng::seed_from_u64(seed);
+
+        (0..amount).map(|_| randomizer.next(&mut rng)).collect()
+    }
+
+    #[test]
+    fn same_seed_same_pieces() {
+        for kind in RandomizerKind::ALL {
+            assert_eq!(pieces(kind, 42, 200), pieces(kind, 42, 200));
+            assert_ne!(pieces(kind, 42, 200), pieces(kind, 43, 200));
+        }
+    }
+
+    #[test]
+    fn bags_deal_every_piece_once_per_bag() {
+        for (kind, bags) in [(RandomizerKind::Bag7, 1), (RandomizerKind::Bag14, 2)] {
+            for bag in pieces(kind, 7, 7 * bags * 20).chunks(7 * bags) {
+                for piece in PieceType::ALL {
+                    assert_eq!(bag.iter().filter(|&&p| p == piece).count(), bags);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn first_piece_leaves_no_overhang() {
+        for kind in RandomizerKind::ALL {
+            for seed in 0..50 {
+                assert!(!OVERHANG_PIECES.contains(&pieces(kind, seed, 1)[0]));
+            }
+        }
+    }
+
+    #[test]
+    fn tgm_rerolls_pieces_in_the_history() {
+        // Without rerolls, about half of the pieces would be one of the last 4.
+        for (kind, most) in [(RandomizerKind::Tgm1, 0.15), (RandomizerKind::Tgm3, 0.05)] {
+            let dealt = pieces(kind, 1, 5000);
+
+            let repeats = dealt
+                .windows(5)
+                .filter(|window| window[..4].contains(&window[4]))
+                .count();
+
+            assert!((repeats as f64) < dealt.len() as f64 * most);
+        }
+
+        let random = pieces(RandomizerKind::Random, 1, 5000);
+        let repeats = random
+            .windows(5)
+            .filter(|window| window[..4].contains(&window[4]))
+            .count();
+
+        assert!(repeats as f64 > random.len() as f64 * 0.4);
+    }
+}
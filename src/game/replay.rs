@@ -0,0 +1,551 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::config::{config_dir, Config};
+use super::engine::Engine;
+use super::input::Action;
+
+/// The version of the replay format, replays of other versions can not be played back.
+pub const REPLAY_VERSION: u32 = 1;
+
+// Every compact replay file starts with these bytes, so we can tell it apart from a JSON one.
+const MAGIC: &[u8; 4] = b"TRRP";
+
+// The actions in the order of their numbers in the compact format, never reorder these.
+const ACTIONS: [Action; 8] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::SoftDrop,
+    Action::HardDrop,
+    Action::RotateClockwise,
+    Action::RotateCounterClockwise,
+    Action::Rotate180,
+    Action::Hold,
+];
+
+/// A single press or release of an action, on the frame it happened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputEvent {
+    // How many frames the game had run when the action was pressed or released.
+    pub frame: u64,
+    pub action: Action,
+    pub pressed: bool,
+}
+
+/// The format replays are saved in, selectable in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayFormat {
+    // A small binary file, a few bytes for every input.
+    Compact,
+    // A readable JSON file, a lot bigger.
+    Json,
+}
+
+impl ReplayFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Compact => "replay",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Everything needed to play a game again exactly like it happened.
+/// The engine is deterministic, so the seed, the settings and the inputs are enough.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    // The settings of the game, with the seed filled in.
+    pub config: Config,
+    pub inputs: Vec<InputEvent>,
+    // How many frames the game lasted.
+    pub frames: u64,
+}
+
+/// Everything that can go wrong while saving or loading a replay.
+#[derive(Debug)]
+pub enum ReplayError {
+    Io { path: PathBuf, source: io::Error },
+    // The file is not a replay, or it is broken.
+    Format { path: PathBuf, message: String },
+    // The replay was recorded with another version of the replay format.
+    Version { path: PathBuf, version: u32 },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Self::Format { path, message } => {
+                write!(f, "{}: Not a valid replay, {}", path.display(), message)
+            }
+            Self::Version { path, version } => write!(
+                f,
+                "{}: The replay has version {}, but this game can only play version {}.",
+                path.display(),
+                version,
+                REPLAY_VERSION
+            ),
+        }
+    }
+}
+
+impl Error for ReplayError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Records every press and release of a game for its replay, and passes them on to the engine.
+/// The inputs are kept here and not in the engine, so cloning an engine does not copy the whole game.
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    pub inputs: Vec<InputEvent>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Presses the action on the engine, and records it if the engine took it.
+    pub fn press(&mut self, engine: &mut Engine, action: Action) {
+        if engine.press(action) {
+            self.record(engine, action, true);
+        }
+    }
+
+    /// Releases the action on the engine, and records it if it was held.
+    pub fn release(&mut self, engine: &mut Engine, action: Action) {
+        if engine.release(action) {
+            self.record(engine, action, false);
+        }
+    }
+
+    /// Releases every held action on the engine.
+    pub fn release_all(&mut self, engine: &mut Engine) {
+        for action in engine.held().to_vec() {
+            self.release(engine, action);
+        }
+
+        engine.release_all();
+    }
+
+    /// The replay of the game the engine played so far, with the recorded inputs.
+    pub fn replay(&self, engine: &Engine) -> Replay {
+        let mut config = engine.config.clone();
+        config.seed = Some(engine.seed);
+
+        Replay {
+            version: REPLAY_VERSION,
+            seed: engine.seed,
+            config,
+            inputs: self.inputs.clone(),
+            frames: engine.frame,
+        }
+    }
+
+    /// Remembers the press or release on the current frame of the engine.
+    fn record(&mut self, engine: &Engine, action: Action, pressed: bool) {
+        self.inputs.push(InputEvent {
+            frame: engine.frame,
+            action,
+            pressed,
+        });
+    }
+}
+
+impl Replay {
+    /// A fresh engine for the game of the replay, before its first frame.
+    pub fn engine(&self) -> Engine {
+        let mut config = self.config.clone();
+        config.seed = Some(self.seed);
+
+        Engine::new(config)
+    }
+
+    /// The replay in the compact format.
+    /// After the header and the settings as JSON, every input is the frames since the last input
+    /// as a variable length number, followed by a byte for the action and if it was pressed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let config = serde_json::to_vec(&self.config).unwrap_or_default();
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        write_varint(&mut bytes, self.frames);
+        write_varint(&mut bytes, config.len() as u64);
+        bytes.extend_from_slice(&config);
+        write_varint(&mut bytes, self.inputs.len() as u64);
+
+        let mut last_frame = 0;
+
+        for input in &self.inputs {
+            write_varint(&mut bytes, input.frame - last_frame);
+            last_frame = input.frame;
+
+            let action = ACTIONS.iter().position(|&a| a == input.action).unwrap_or(0) as u8;
+            bytes.push(action << 1 | u8::from(input.pressed));
+        }
+
+        bytes
+    }
+
+    /// Reads a replay in the compact format, the error is a description of what is wrong.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes, position: 0 };
+
+        if reader.take(4)? != MAGIC {
+            return Err("it does not start like a replay.".to_string());
+        }
+
+        let version = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+        let seed = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        let frames = reader.varint()?;
+
+        let config_length = reader.varint()? as usize;
+        let config = serde_json::from_slice(reader.take(config_length)?)
+            .map_err(|e| format!("the settings are broken, {}.", e))?;
+
+        let input_count = reader.varint()?;
+        let mut inputs = Vec::new();
+        let mut frame = 0;
+
+        for _ in 0..input_count {
+            frame = reader
+                .varint()?
+                .checked_add(frame)
+                .ok_or_else(|| "an input is too late.".to_string())?;
+
+            let byte = reader.take(1)?[0];
+            let action = *ACTIONS
+                .get(usize::from(byte >> 1))
+                .ok_or_else(|| format!("there is no action number {}.", byte >> 1))?;
+
+            inputs.push(InputEvent {
+                frame,
+                action,
+                pressed: byte & 1 == 1,
+            });
+        }
+
+        let replay = Self {
+            version,
+            seed,
+            config,
+            inputs,
+            frames,
+        };
+
+        replay.check_inputs()?;
+
+        Ok(replay)
+    }
+
+    /// Checks that the inputs are in order and none of them come after the last frame.
+    /// Playing back a broken replay like that would never reach its end.
+    fn check_inputs(&self) -> Result<(), String> {
+        let mut frame = 0;
+
+        for input in &self.inputs {
+            if input.frame < frame {
+                return Err(format!(
+                    "the input on frame {} is out of order.",
+                    input.frame
+                ));
+            }
+
+            if input.frame > self.frames {
+                return Err(format!(
+                    "the input on frame {} comes after the last frame {}.",
+                    input.frame, self.frames
+                ));
+            }
+
+            frame = input.frame;
+        }
+
+        Ok(())
+    }
+
+    /// Saves the replay to the file, in the given format.
+    pub fn save(&self, path: &Path, format: ReplayFormat) -> Result<(), ReplayError> {
+        let bytes = match format {
+            ReplayFormat::Compact => self.to_bytes(),
+            ReplayFormat::Json => {
+                serde_json::to_vec_pretty(self).map_err(|e| ReplayError::Format {
+                    path: path.to_path_buf(),
+                    message: e.to_string(),
+                })?
+            }
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|source| ReplayError::Io {
+                path: dir.to_path_buf(),
+                source,
+            })?;
+        }
+
+        fs::write(path, bytes).map_err(|source| ReplayError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Loads a replay from the file, in either format.
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let bytes = fs::read(path).map_err(|source| ReplayError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let replay = if bytes.starts_with(MAGIC) {
+            Self::from_bytes(&bytes)
+        } else {
+            serde_json::from_slice::<Self>(&bytes)
+                .map_err(|e| e.to_string())
+                .and_then(|replay| replay.check_inputs().map(|()| replay))
+        }
+        .map_err(|message| ReplayError::Format {
+            path: path.to_path_buf(),
+            message,
+        })?;
+
+        if replay.version != REPLAY_VERSION {
+            return Err(ReplayError::Version {
+                path: path.to_path_buf(),
+                version: replay.version,
+            });
+        }
+
+        Ok(replay)
+    }
+}
+
+/// Where the replay of a game is saved, in the replays folder next to the config file.
+/// The name has the time the game was saved at, and the seed.
+pub fn replay_path(seed: u64, format: ReplayFormat) -> Option<PathBuf> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+
+    config_dir().map(|dir| {
+        dir.join("replays")
+            .join(format!("{}-{}.{}", time, seed, format.extension()))
+    })
+}
+
+/// Plays a replay back on an engine, frame by frame.
+/// The engine is not part of this, so a frontend can draw it like any other game.
+#[derive(Clone)]
+pub struct Playback {
+    pub replay: Replay,
+    // The index of the next input to press or release.
+    next_input: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_input: 0,
+        }
+    }
+
+    /// Checks if the engine reached the end of the replay.
+    /// The inputs of the last frame count too, since the game can end with a key press instead of a frame.
+    pub fn is_finished(&self, engine: &Engine) -> bool {
+        engine.game_over
+            || (engine.frame >= self.replay.frames && self.next_input >= self.replay.inputs.len())
+    }
+
+    /// Plays the inputs of the current frame on the engine and advances it by a frame.
+    pub fn step(&mut self, engine: &mut Engine) {
+        if self.is_finished(engine) {
+            return;
+        }
+
+        while let Some(input) = self.replay.inputs.get(self.next_input) {
+            if input.frame > engine.frame {
+                break;
+            }
+
+            if input.pressed {
+                engine.press(input.action);
+            } else {
+                engine.release(input.action);
+            }
+
+            self.next_input += 1;
+        }
+
+        if engine.frame < self.replay.frames {
+            engine.tick();
+        } else {
+            // Inputs after the last frame can never be played, so the replay is over.
+            self.next_input = self.replay.inputs.len();
+        }
+    }
+
+    /// Jumps to the frame of the replay.
+    /// Going back starts the game over and plays it again up to the frame, since the engine can only go forward.
+    pub fn seek(&mut self, engine: &mut Engine, frame: u64) {
+        let frame = frame.min(self.replay.frames);
+
+        if frame < engine.frame {
+            *engine = self.replay.engine();
+            self.next_input = 0;
+        }
+
+        let to_end = frame == self.replay.frames;
+
+        while (engine.frame < frame || to_end) && !self.is_finished(engine) {
+            self.step(engine);
+        }
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+// Reads the compact format from the front to the back.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, amount: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(amount)
+            .ok_or_else(|| "the file ends too early.".to_string())?;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or_else(|| "the file ends too early.".to_string())?;
+
+        self.position = end;
+
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err("a number is too long.".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn late_input_replay() -> Replay {
+        let engine = Engine::new(Config {
+            seed: Some(1),
+            ..Config::default()
+        });
+        let mut replay = Recorder::new().replay(&engine);
+
+        replay.frames = 10;
+        replay.inputs.push(InputEvent {
+            frame: 20,
+            action: Action::HardDrop,
+            pressed: true,
+        });
+
+        replay
+    }
+
+    #[test]
+    fn inputs_after_the_last_frame_are_rejected() {
+        let bytes = late_input_replay().to_bytes();
+
+        assert!(Replay::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn playback_ends_with_inputs_after_the_last_frame() {
+        let replay = late_input_replay();
+        let mut engine = replay.engine();
+        let mut playback = Playback::new(replay);
+
+        playback.seek(&mut engine, u64::MAX);
+
+        assert!(playback.is_finished(&engine));
+        assert_eq!(engine.frame, 10);
+    }
+
+    #[test]
+    fn replays_play_back_the_same_game() {
+        let mut engine = Engine::new(Config {
+            seed: Some(7),
+            ..Config::default()
+        });
+        let mut recorder = Recorder::new();
+        let actions = [
+            Action::MoveLeft,
+            Action::RotateClockwise,
+            Action::HardDrop,
+            Action::MoveRight,
+            Action::Hold,
+            Action::SoftDrop,
+            Action::Rotate180,
+            Action::HardDrop,
+        ];
+
+        for (i, &action) in actions.iter().cycle().take(80).enumerate() {
+            recorder.press(&mut engine, action);
+
+            for _ in 0..i % 5 {
+                engine.tick();
+            }
+
+            recorder.release(&mut engine, action);
+            engine.tick();
+        }
+
+        let bytes = recorder.replay(&engine).to_bytes();
+        let replay = Replay::from_bytes(&bytes).unwrap();
+        let mut played = replay.engine();
+        let mut playback = Playback::new(replay);
+
+        playback.seek(&mut played, u64::MAX);
+
+        assert!(playback.is_finished(&played));
+        assert_eq!(played.frame, engine.frame);
+        assert_eq!(played.score, engine.score);
+        assert_eq!(played.lines_cleared, engine.lines_cleared);
+        assert_eq!(played.piece_count, engine.piece_count);
+        assert_eq!(played.board.rows, engine.board.rows);
+        assert_eq!(played.game_over, engine.game_over);
+    }
+}
@@ -0,0 +1,434 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::board::Board;
+use super::pieces::{Piece, PieceType};
+
+/// A rotation system decides how the pieces look in each of their four rotation states,
+/// where they spawn and which "kicks" are tried if a rotation does not fit right away.
+/// The rotation states are 0 (spawn), 1 (R, clockwise), 2 (180°) and 3 (L, counter-clockwise).
+pub trait RotationSystem: Send + Sync {
+    /// The blocks of the piece in every rotation state,
+    /// as (row, column) coordinates relative to the top left corner of its bounding box.
+    fn orientations(&self, piece_type: PieceType) -> [[(usize, usize); 4]; 4];
+
+    /// Where the top left corner of the bounding box is placed when the piece spawns, on a 10 column board.
+    fn spawn_offset(&self, piece_type: PieceType) -> (isize, isize);
+
+    /// The (row, column) shifts to try in order when rotating the piece into the new rotation state.
+    /// The first one that fits is used, if none fit the rotation fails.
+    fn kicks(&self, piece: &Piece, board: &Board, rotation: usize) -> Vec<(isize, isize)>;
+}
+
+/// The built-in rotation systems, selectable in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RotationSystemKind {
+    Classic,
+    Srs,
+    Ars,
+    Nes,
+}
+
+impl RotationSystemKind {
+    pub fn get(self) -> Arc<dyn RotationSystem> {
+        match self {
+            Self::Classic => Arc::new(Classic),
+            Self::Srs => Arc::new(Srs),
+            Self::Ars => Arc::new(Ars),
+            Self::Nes => Arc::new(Nes),
+        }
+    }
+}
+
+/// This game's own rotation system, the pieces rotate around their top left corner and never kick.
+/// The pictures of every rotation are in README.md.
+pub struct Classic;
+
+impl RotationSystem for Classic {
+    fn orientations(&self, piece_type: PieceType) -> [[(usize, usize); 4]; 4] {
+        match piece_type {
+            PieceType::I => [
+                [(0, 0), (0, 1), (0, 2), (0, 3)],
+                [(0, 0), (1, 0), (2, 0), (3, 0)],
+                [(0, 0), (0, 1), (0, 2), (0, 3)],
+                [(0, 0), (1, 0), (2, 0), (3, 0)],
+            ],
+            PieceType::L => [
+                [(1, 1), (1, 0), (1, 2), (0, 2)],
+                [(0, 0), (1, 0), (2, 0), (2, 1)],
+                [(0, 0), (0, 1), (0, 2), (1, 0)],
+                [(0, 0), (0, 1), (1, 1), (2, 1)],
+            ],
+            PieceType::J => [
+                [(0, 0), (1, 0), (1, 1), (1, 2)],
+                [(0, 0), (0, 1), (1, 0), (2, 0)],
+                [(0, 0), (0, 1), (0, 2), (1, 2)],
+                [(0, 1), (1, 1), (2, 1), (2, 0)],
+            ],
+            PieceType::S => [
+                [(1, 0), (1, 1), (0, 1), (0, 2)],
+                [(0, 0), (1, 0), (1, 1), (2, 1)],
+                [(1, 0), (1, 1), (0, 1), (0, 2)],
+                [(0, 0), (1, 0), (1, 1), (2, 1)],
+            ],
+            PieceType::Z => [
+                [(0, 0), (0, 1), (1, 1), (1, 2)],
+                [(1, 0), (2, 0), (0, 1), (1, 1)],
+                [(0, 0), (0, 1), (1, 1), (1, 2)],
+                [(1, 0), (2, 0), (0, 1), (1, 1)],
+            ],
+            PieceType::O => [[(0, 0), (0, 1), (1, 0), (1, 1)]; 4],
+            PieceType::T => [
+                [(1, 0), (1, 1), (0, 1), (1, 2)],
+                [(0, 0), (1, 0), (2, 0), (1, 1)],
+                [(0, 0), (0, 1), (0, 2), (1, 1)],
+                [(1, 0), (0, 1), (1, 1), (2, 1)],
+            ],
+        }
+    }
+
+    fn spawn_offset(&self, _piece_type: PieceType) -> (isize, isize) {
+        (0, 3)
+    }
+
+    fn kicks(&self, _piece: &Piece, _board: &Board, _rotation: usize) -> Vec<(isize, isize)> {
+        vec![(0, 0)]
+    }
+}
+
+/// The Super Rotation System of the modern games, see https://tetris.wiki/Super_Rotation_System.
+pub struct Srs;
+
+// The kicks are (row, column) shifts, tried in order until one fits.
+// Note that rows go down, so an upwards kick has a negative row.
+
+const JLSTZ_KICKS: [[(isize, isize); 5]; 8] = [
+    // 0 -> R
+    [(0, 0), (0, -1), (-1, -1), (2, 0), (2, -1)],
+    // R -> 0
+    [(0, 0), (0, 1), (1, 1), (-2, 0), (-2, 1)],
+    // R -> 2
+    [(0, 0), (0, 1), (1, 1), (-2, 0), (-2, 1)],
+    // 2 -> R
+    [(0, 0), (0, -1), (-1, -1), (2, 0), (2, -1)],
+    // 2 -> L
+    [(0, 0), (0, 1), (-1, 1), (2, 0), (2, 1)],
+    // L -> 2
+    [(0, 0), (0, -1), (1, -1), (-2, 0), (-2, -1)],
+    // L -> 0
+    [(0, 0), (0, -1), (1, -1), (-2, 0), (-2, -1)],
+    // 0 -> L
+    [(0, 0), (0, 1), (-1, 1), (2, 0), (2, 1)],
+];
+
+const I_KICKS: [[(isize, isize); 5]; 8] = [
+    // 0 -> R
+    [(0, 0), (0, -2), (0, 1), (1, -2), (-2, 1)],
+    // R -> 0
+    [(0, 0), (0, 2), (0, -1), (-1, 2), (2, -1)],
+    // R -> 2
+    [(0, 0), (0, -1), (0, 2), (-2, -1), (1, 2)],
+    // 2 -> R
+    [(0, 0), (0, 1), (0, -2), (2, 1), (-1, -2)],
+    // 2 -> L
+    [(0, 0), (0, 2), (0, -1), (-1, 2), (2, -1)],
+    // L -> 2
+    [(0, 0), (0, -2), (0, 1), (1, -2), (-2, 1)],
+    // L -> 0
+    [(0, 0), (0, 1), (0, -2), (2, 1), (-1, -2)],
+    // 0 -> L
+    [(0, 0), (0, -1), (0, 2), (-2, -1), (1, 2)],
+];
+
+// SRS itself has no 180° rotations, these are the kicks most modern games settled on.
+const HALF_TURN_KICKS: [[(isize, isize); 6]; 4] = [
+    // 0 -> 2
+    [(0, 0), (-1, 0), (-1, 1), (-1, -1), (0, 1), (0, -1)],
+    // R -> L
+    [(0, 0), (0, 1), (-2, 1), (-1, 1), (-2, 0), (-1, 0)],
+    // 2 -> 0
+    [(0, 0), (1, 0), (1, -1), (1, 1), (0, -1), (0, 1)],
+    // L -> R
+    [(0, 0), (0, -1), (-2, -1), (-1, -1), (-2, 0), (-1, 0)],
+];
+
+impl RotationSystem for Srs {
+    fn orientations(&self, piece_type: PieceType) -> [[(usize, usize); 4]; 4] {
+        match piece_type {
+            PieceType::I => [
+                [(1, 0), (1, 1), (1, 2), (1, 3)],
+                [(0, 2), (1, 2), (2, 2), (3, 2)],
+                [(2, 0), (2, 1), (2, 2), (2, 3)],
+                [(0, 1), (1, 1), (2, 1), (3, 1)],
+            ],
+            PieceType::L => [
+                [(0, 2), (1, 0), (1, 1), (1, 2)],
+                [(0, 1), (1, 1), (2, 1), (2, 2)],
+                [(1, 0), (1, 1), (1, 2), (2, 0)],
+                [(0, 0), (0, 1), (1, 1), (2, 1)],
+            ],
+            PieceType::J => [
+                [(0, 0), (1, 0), (1, 1), (1, 2)],
+                [(0, 1), (0, 2), (1, 1), (2, 1)],
+                [(1, 0), (1, 1), (1, 2), (2, 2)],
+                [(0, 1), (1, 1), (2, 0), (2, 1)],
+            ],
+            PieceType::S => [
+                [(0, 1), (0, 2), (1, 0), (1, 1)],
+                [(0, 1), (1, 1), (1, 2), (2, 2)],
+                [(1, 1), (1, 2), (2, 0), (2, 1)],
+                [(0, 0), (1, 0), (1, 1), (2, 1)],
+            ],
+            PieceType::Z => [
+                [(0, 0), (0, 1), (1, 1), (1, 2)],
+                [(0, 2), (1, 1), (1, 2), (2, 1)],
+                [(1, 0), (1, 1), (2, 1), (2, 2)],
+                [(0, 1), (1, 0), (1, 1), (2, 0)],
+            ],
+            PieceType::O => [[(0, 0), (0, 1), (1, 0), (1, 1)]; 4],
+            PieceType::T => [
+                [(0, 1), (1, 0), (1, 1), (1, 2)],
+                [(0, 1), (1, 1), (1, 2), (2, 1)],
+                [(1, 0), (1, 1), (1, 2), (2, 1)],
+                [(0, 1), (1, 0), (1, 1), (2, 1)],
+            ],
+        }
+    }
+
+    fn spawn_offset(&self, piece_type: PieceType) -> (isize, isize) {
+        match piece_type {
+            // The I piece lies in the second row of its box, so we move it up one row.
+            PieceType::I => (-1, 3),
+            PieceType::O => (0, 4),
+            _ => (0, 3),
+        }
+    }
+
+    fn kicks(&self, piece: &Piece, _board: &Board, rotation: usize) -> Vec<(isize, isize)> {
+        let from = piece.rotations;
+
+        // The O piece does not need any kicks, it looks the same in every rotation.
+        if piece.piece_type == PieceType::O {
+            return vec![(0, 0)];
+        }
+
+        if (from + 2) % 4 == rotation {
+            return HALF_TURN_KICKS[from].to_vec();
+        }
+
+        let index = if (from + 1) % 4 == rotation {
+            from * 2
+        } else {
+            // Counter-clockwise rotations are stored right after the clockwise rotation they undo,
+            // so R -> 0 comes right after 0 -> R.
+            rotation * 2 + 1
+        };
+
+        if piece.piece_type == PieceType::I {
+            I_KICKS[index].to_vec()
+        } else {
+            JLSTZ_KICKS[index].to_vec()
+        }
+    }
+}
+
+/// The Arika Rotation System of the Tetris The Grand Master games, see https://tetris.wiki/Arika_Rotation_System.
+/// The pieces are aligned to the bottom of their box and only kick one column to the right or left.
+pub struct Ars;
+
+impl RotationSystem for Ars {
+    fn orientations(&self, piece_type: PieceType) -> [[(usize, usize); 4]; 4] {
+        match piece_type {
+            PieceType::I => [
+                [(1, 0), (1, 1), (1, 2), (1, 3)],
+                [(0, 2), (1, 2), (2, 2), (3, 2)],
+                [(1, 0), (1, 1), (1, 2), (1, 3)],
+                [(0, 2), (1, 2), (2, 2), (3, 2)],
+            ],
+            PieceType::L => [
+                [(1, 0), (1, 1), (1, 2), (2, 0)],
+                [(0, 0), (0, 1), (1, 1), (2, 1)],
+                [(1, 2), (2, 0), (2, 1), (2, 2)],
+                [(0, 1), (1, 1), (2, 1), (2, 2)],
+            ],
+            PieceType::J => [
+                [(1, 0), (1, 1), (1, 2), (2, 2)],
+                [(0, 1), (1, 1), (2, 0), (2, 1)],
+                [(1, 0), (2, 0), (2, 1), (2, 2)],
+                [(0, 1), (0, 2), (1, 1), (2, 1)],
+            ],
+            PieceType::S => [
+                [(1, 1), (1, 2), (2, 0), (2, 1)],
+                [(0, 0), (1, 0), (1, 1), (2, 1)],
+                [(1, 1), (1, 2), (2, 0), (2, 1)],
+                [(0, 0), (1, 0), (1, 1), (2, 1)],
+            ],
+            PieceType::Z => [
+                [(1, 0), (1, 1), (2, 1), (2, 2)],
+                [(0, 2), (1, 1), (1, 2), (2, 1)],
+                [(1, 0), (1, 1), (2, 1), (2, 2)],
+                [(0, 2), (1, 1), (1, 2), (2, 1)],
+            ],
+            PieceType::O => [[(0, 0), (0, 1), (1, 0), (1, 1)]; 4],
+            PieceType::T => [
+                [(1, 0), (1, 1), (1, 2), (2, 1)],
+                [(0, 1), (1, 0), (1, 1), (2, 1)],
+                [(1, 1), (2, 0), (2, 1), (2, 2)],
+                [(0, 1), (1, 1), (1, 2), (2, 1)],
+            ],
+        }
+    }
+
+    fn spawn_offset(&self, piece_type: PieceType) -> (isize, isize) {
+        match piece_type {
+            // The pieces are at the bottom of their box, so we move the box up one row.
+            PieceType::O => (0, 4),
+            _ => (-1, 3),
+        }
+    }
+
+    fn kicks(&self, piece: &Piece, board: &Board, rotation: usize) -> Vec<(isize, isize)> {
+        // The I piece never kicks.
+        if piece.piece_type == PieceType::I || piece.piece_type == PieceType::O {
+            return vec![(0, 0)];
+        }
+
+        // The L, J and T pieces may not kick if the first blocked cell
+        // (going row by row, left to right) is in the center column of their box.
+        if [PieceType::L, PieceType::J, PieceType::T].contains(&piece.piece_type) {
+            let mut blocks = piece.orientations[rotation];
+            blocks.sort_unstable();
+
+            for (y, x) in blocks {
+                let row = y as isize + piece.offset.0;
+                let column = x as isize + piece.offset.1;
+
+                let blocked =
+                    row < 0 || column < 0 || board.collides(&[(row as usize, column as usize)]);
+
+                if blocked {
+                    if x == 1 {
+                        return vec![(0, 0)];
+                    }
+                    break;
+                }
+            }
+        }
+
+        vec![(0, 0), (0, 1), (0, -1)]
+    }
+}
+
+/// The rotation system of the NES version, see https://tetris.wiki/Nintendo_Rotation_System.
+/// There are no kicks at all, a rotation either fits or it does not.
+pub struct Nes;
+
+impl RotationSystem for Nes {
+    fn orientations(&self, piece_type: PieceType) -> [[(usize, usize); 4]; 4] {
+        match piece_type {
+            PieceType::I => [
+                [(2, 0), (2, 1), (2, 2), (2, 3)],
+                [(0, 2), (1, 2), (2, 2), (3, 2)],
+                [(2, 0), (2, 1), (2, 2), (2, 3)],
+                [(0, 2), (1, 2), (2, 2), (3, 2)],
+            ],
+            PieceType::L => [
+                [(1, 0), (1, 1), (1, 2), (2, 0)],
+                [(0, 0), (0, 1), (1, 1), (2, 1)],
+                [(0, 2), (1, 0), (1, 1), (1, 2)],
+                [(0, 1), (1, 1), (2, 1), (2, 2)],
+            ],
+            PieceType::J => [
+                [(1, 0), (1, 1), (1, 2), (2, 2)],
+                [(0, 1), (1, 1), (2, 0), (2, 1)],
+                [(0, 0), (1, 0), (1, 1), (1, 2)],
+                [(0, 1), (0, 2), (1, 1), (2, 1)],
+            ],
+            PieceType::S => [
+                [(1, 1), (1, 2), (2, 0), (2, 1)],
+                [(0, 1), (1, 1), (1, 2), (2, 2)],
+                [(1, 1), (1, 2), (2, 0), (2, 1)],
+                [(0, 1), (1, 1), (1, 2), (2, 2)],
+            ],
+            PieceType::Z => [
+                [(1, 0), (1, 1), (2, 1), (2, 2)],
+                [(0, 2), (1, 1), (1, 2), (2, 1)],
+                [(1, 0), (1, 1), (2, 1), (2, 2)],
+                [(0, 2), (1, 1), (1, 2), (2, 1)],
+            ],
+            PieceType::O => [[(0, 0), (0, 1), (1, 0), (1, 1)]; 4],
+            PieceType::T => [
+                [(1, 0), (1, 1), (1, 2), (2, 1)],
+                [(0, 1), (1, 0), (1, 1), (2, 1)],
+                [(0, 1), (1, 0), (1, 1), (1, 2)],
+                [(0, 1), (1, 1), (1, 2), (2, 1)],
+            ],
+        }
+    }
+
+    fn spawn_offset(&self, piece_type: PieceType) -> (isize, isize) {
+        match piece_type {
+            PieceType::I => (-2, 3),
+            PieceType::O => (0, 4),
+            _ => (-1, 3),
+        }
+    }
+
+    fn kicks(&self, _piece: &Piece, _board: &Board, _rotation: usize) -> Vec<(isize, isize)> {
+        vec![(0, 0)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srs_kicks_off_the_wall() {
+        let board = Board::default();
+        let mut piece = Piece::new(PieceType::T, &Srs, &board);
+
+        // Standing upright against the left wall, the flat side of the T has no room to the left.
+        piece.rotations = 1;
+        piece.offset = (10, -1);
+
+        assert!(Piece::rotate_piece(&mut piece, &board, &Srs, true));
+        assert_eq!(piece.rotations, 2);
+        assert_eq!(piece.last_kick, Some((0, 1)));
+        assert_eq!(piece.offset, (10, 0));
+    }
+
+    #[test]
+    fn srs_kicks_up_off_the_floor() {
+        let board = Board::default();
+        let mut piece = Piece::new(PieceType::T, &Srs, &board);
+        let floor = board.total_height() as isize;
+
+        piece.offset = (floor - 2, 3);
+
+        // The first two kicks reach below the floor, the third one moves the piece up and to the left.
+        assert!(Piece::rotate_piece(&mut piece, &board, &Srs, true));
+        assert_eq!(piece.rotations, 1);
+        assert_eq!(piece.last_kick, Some((-1, -1)));
+        assert_eq!(piece.offset, (floor - 3, 2));
+    }
+
+    #[test]
+    fn srs_rotation_fails_without_room() {
+        let mut board = Board::default();
+        let mut piece = Piece::new(PieceType::I, &Srs, &board);
+        let bottom = board.total_height() - 1;
+
+        // A horizontal I piece in a one row high gap, it cannot stand up anywhere.
+        for x in 0..board.width {
+            board.fill(bottom - 1, x, (0, 0, 0));
+        }
+        piece.offset = (bottom as isize - 1, 3);
+
+        assert!(!Piece::rotate_piece(&mut piece, &board, &Srs, true));
+        assert_eq!(piece.rotations, 0);
+        assert_eq!(piece.offset, (bottom as isize - 1, 3));
+    }
+}
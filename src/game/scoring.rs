@@ -0,0 +1,488 @@
+use serde::{Deserialize, Serialize};
+
+use super::clear::{LineClear, TSpin};
+use super::config::Config;
+
+/// A scoring rule decides how many points line clears, T-Spins and drops are worth.
+/// Rules can keep their own state, like a combo counter, since they are asked about every lock.
+pub trait ScoringRule: Send {
+    /// The points for a line clear or T-Spin.
+    /// The level is the one from before the clear, and the soft drop is how many rows the piece was soft dropped.
+    fn clear_points(&mut self, clear: &LineClear, level: u128, soft_drop: u128) -> u128;
+
+    /// Called for every locked piece that did not clear any lines.
+    fn no_clear(&mut self) {}
+
+    /// The points for soft dropping a piece by the given amount of rows.
+    fn soft_drop_points(&self, rows: u128) -> u128 {
+        rows
+    }
+
+    /// The points for hard dropping a piece by the given amount of rows.
+    fn hard_drop_points(&self, rows: u128) -> u128 {
+        rows
+    }
+
+    /// The grade for the score, for rules that have grades.
+    fn grade(&self, _score: u128) -> Option<&'static str> {
+        None
+    }
+
+    /// A copy of the rule with the same state, so the engine can be cloned.
+    fn clone_box(&self) -> Box<dyn ScoringRule>;
+}
+
+impl Clone for Box<dyn ScoringRule> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// The built-in scoring rules, selectable in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScoringKind {
+    Nes,
+    Guideline,
+    Tgm,
+    // The table in `custom_scoring`.
+    Custom,
+}
+
+impl ScoringKind {
+    pub fn get(self, config: &Config) -> Box<dyn ScoringRule> {
+        match self {
+            Self::Nes => Box::new(Nes),
+            Self::Guideline => Box::new(Guideline),
+            Self::Tgm => Box::new(Tgm::new()),
+            Self::Custom => Box::new(Custom(config.custom_scoring.clone())),
+        }
+    }
+}
+
+// The T-Spins did not exist in the NES games, so these use the guideline values.
+fn t_spin_points(t_spin: TSpin, lines: u8) -> u128 {
+    match (t_spin, lines) {
+        (TSpin::Mini, 0) => 100,
+        (TSpin::Mini, 1) => 200,
+        (TSpin::Mini, _) => 400,
+        (TSpin::Full, 0) => 400,
+        (TSpin::Full, 1) => 800,
+        (TSpin::Full, 2) => 1200,
+        (TSpin::Full, _) => 1600,
+        (TSpin::None, _) => 0,
+    }
+}
+
+/// The table of NES Tetris, with the T-Spins of the modern games on top.
+#[derive(Clone)]
+pub struct Nes;
+
+impl ScoringRule for Nes {
+    fn clear_points(&mut self, clear: &LineClear, level: u128, _soft_drop: u128) -> u128 {
+        if clear.t_spin != TSpin::None {
+            return t_spin_points(clear.t_spin, clear.lines) * level;
+        }
+
+        let points = match clear.lines {
+            1 => 40,
+            2 => 100,
+            3 => 300,
+            4 => 1200,
+            _ => 0,
+        };
+
+        points * level
+    }
+
+    fn clone_box(&self) -> Box<dyn ScoringRule> {
+        Box::new(self.clone())
+    }
+}
+
+/// The modern games, with Back-to-Backs, combos and perfect clears.
+#[derive(Clone)]
+pub struct Guideline;
+
+impl ScoringRule for Guideline {
+    fn clear_points(&mut self, clear: &LineClear, level: u128, _soft_drop: u128) -> u128 {
+        let mut points = if clear.t_spin != TSpin::None {
+            t_spin_points(clear.t_spin, clear.lines)
+        } else {
+            match clear.lines {
+                1 => 100,
+                2 => 300,
+                3 => 500,
+                4 => 800,
+                _ => 0,
+            }
+        };
+
+        // Back-to-Backs are worth one and a half times as much.
+        if clear.back_to_back {
+            points = points * 3 / 2;
+        }
+
+        if clear.perfect_clear {
+            points += match clear.lines {
+                1 => 800,
+                2 => 1200,
+                3 => 1800,
+                _ if clear.back_to_back => 3200,
+                _ => 2000,
+            };
+        }
+
+        points += 50 * u128::from(clear.combo);
+
+        points * level
+    }
+
+    fn hard_drop_points(&self, rows: u128) -> u128 {
+        rows * 2
+    }
+
+    fn clone_box(&self) -> Box<dyn ScoringRule> {
+        Box::new(self.clone())
+    }
+}
+
+// The score you need for every grade of Tetris The Grand Master, from 9 up to S9.
+const TGM_GRADES: [(u128, &str); 18] = [
+    (0, "9"),
+    (400, "8"),
+    (800, "7"),
+    (1400, "6"),
+    (2000, "5"),
+    (3500, "4"),
+    (5500, "3"),
+    (8000, "2"),
+    (12000, "1"),
+    (16000, "S1"),
+    (22000, "S2"),
+    (30000, "S3"),
+    (40000, "S4"),
+    (52000, "S5"),
+    (66000, "S6"),
+    (82000, "S7"),
+    (100000, "S8"),
+    (120000, "S9"),
+];
+
+/// The scoring of Tetris The Grand Master, where your score decides your grade.
+/// A line clear is worth (level + lines) / 4, rounded up, plus the soft dropped rows,
+/// times the lines, times the combo, times 4 for a perfect clear.
+/// Drops are not worth anything on their own, and there are no bonuses for T-Spins.
+#[derive(Clone)]
+pub struct Tgm {
+    // Starts at 1, and grows by 2 for every line after the first of every clear in a row.
+    combo: u128,
+}
+
+impl Tgm {
+    pub fn new() -> Self {
+        Self { combo: 1 }
+    }
+}
+
+impl Default for Tgm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScoringRule for Tgm {
+    fn clear_points(&mut self, clear: &LineClear, level: u128, soft_drop: u128) -> u128 {
+        let lines = u128::from(clear.lines);
+
+        if lines == 0 {
+            return 0;
+        }
+
+        self.combo += 2 * lines - 2;
+
+        let bravo = if clear.perfect_clear { 4 } else { 1 };
+
+        ((level + lines).div_ceil(4) + soft_drop) * lines * self.combo * bravo
+    }
+
+    fn no_clear(&mut self) {
+        self.combo = 1;
+    }
+
+    fn soft_drop_points(&self, _rows: u128) -> u128 {
+        0
+    }
+
+    fn hard_drop_points(&self, _rows: u128) -> u128 {
+        0
+    }
+
+    fn grade(&self, score: u128) -> Option<&'static str> {
+        TGM_GRADES
+            .iter()
+            .rev()
+            .find(|(points, _)| score >= *points)
+            .map(|(_, grade)| *grade)
+    }
+
+    fn clone_box(&self) -> Box<dyn ScoringRule> {
+        Box::new(self.clone())
+    }
+}
+
+/// Your own scoring table, see README.md. Everything you leave out has the guideline value.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringTable {
+    // The points for clearing 1, 2, 3 and 4 lines.
+    pub lines: [u64; 4],
+    // The points for a T-Spin with 0, 1, 2 and 3 lines.
+    pub t_spin: [u64; 4],
+    // The points for a T-Spin Mini with 0, 1 and 2 lines.
+    pub t_spin_mini: [u64; 3],
+    // What the points of a Back-to-Back are multiplied with.
+    pub back_to_back: f64,
+    // The points for every line clear in a row, after the first.
+    pub combo: u64,
+    // The points on top of a perfect clear of 1, 2, 3 and 4 lines.
+    pub perfect_clear: [u64; 4],
+    pub soft_drop: u64,
+    pub hard_drop: u64,
+    // If the points of line clears are multiplied by the level.
+    pub level_multiplier: bool,
+}
+
+impl Default for ScoringTable {
+    fn default() -> Self {
+        Self {
+            lines: [100, 300, 500, 800],
+            t_spin: [400, 800, 1200, 1600],
+            t_spin_mini: [100, 200, 400],
+            back_to_back: 1.5,
+            combo: 50,
+            perfect_clear: [800, 1200, 1800, 2000],
+            soft_drop: 1,
+            hard_drop: 2,
+            level_multiplier: true,
+        }
+    }
+}
+
+/// Scores everything with the table in the config.
+#[derive(Clone)]
+pub struct Custom(pub ScoringTable);
+
+impl ScoringRule for Custom {
+    fn clear_points(&mut self, clear: &LineClear, level: u128, _soft_drop: u128) -> u128 {
+        let table = &self.0;
+        let lines = usize::from(clear.lines);
+
+        let points = match clear.t_spin {
+            TSpin::None => lines.checked_sub(1).map_or(0, |i| table.lines[i.min(3)]),
+            TSpin::Mini => table.t_spin_mini[lines.min(2)],
+            TSpin::Full => table.t_spin[lines.min(3)],
+        };
+
+        let mut points = u128::from(points);
+
+        if clear.back_to_back {
+            points = (points as f64 * table.back_to_back.max(0.0)).round() as u128;
+        }
+
+        if clear.perfect_clear && lines > 0 {
+            points += u128::from(table.perfect_clear[lines.min(4) - 1]);
+        }
+
+        points += u128::from(table.combo) * u128::from(clear.combo);
+
+        if table.level_multiplier {
+            points *= level;
+        }
+
+        points
+    }
+
+    fn soft_drop_points(&self, rows: u128) -> u128 {
+        rows * u128::from(self.0.soft_drop)
+    }
+
+    fn hard_drop_points(&self, rows: u128) -> u128 {
+        rows * u128::from(self.0.hard_drop)
+    }
+
+    fn clone_box(&self) -> Box<dyn ScoringRule> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear(lines: u8, t_spin: TSpin) -> LineClear {
+        LineClear {
+            lines,
+            t_spin,
+            back_to_back: false,
+            combo: 0,
+            perfect_clear: false,
+        }
+    }
+
+    #[test]
+    fn guideline_back_to_backs() {
+        let mut rule = Guideline;
+        let tetris = clear(4, TSpin::None);
+        let t_spin_double = LineClear {
+            back_to_back: true,
+            ..clear(2, TSpin::Full)
+        };
+
+        assert_eq!(rule.clear_points(&tetris, 1, 0), 800);
+        assert_eq!(rule.clear_points(&t_spin_double, 1, 0), 1800);
+        assert_eq!(rule.clear_points(&t_spin_double, 2, 0), 3600);
+    }
+
+    #[test]
+    fn guideline_combos() {
+        let mut rule = Guideline;
+        let single = LineClear {
+            combo: 3,
+            ..clear(1, TSpin::None)
+        };
+
+        assert_eq!(rule.clear_points(&single, 1, 0), 250);
+        assert_eq!(rule.clear_points(&single, 2, 0), 500);
+    }
+
+    #[test]
+    fn guideline_perfect_clears() {
+        let mut rule = Guideline;
+        let tetris = LineClear {
+            perfect_clear: true,
+            ..clear(4, TSpin::None)
+        };
+        let back_to_back_tetris = LineClear {
+            back_to_back: true,
+            ..tetris
+        };
+
+        assert_eq!(rule.clear_points(&tetris, 1, 0), 2800);
+        assert_eq!(rule.clear_points(&back_to_back_tetris, 1, 0), 4400);
+        assert_eq!(
+            rule.clear_points(
+                &LineClear {
+                    perfect_clear: true,
+                    ..clear(1, TSpin::None)
+                },
+                1,
+                0
+            ),
+            900
+        );
+    }
+
+    #[test]
+    fn guideline_drops() {
+        assert_eq!(Guideline.soft_drop_points(5), 5);
+        assert_eq!(Guideline.hard_drop_points(19), 38);
+    }
+
+    #[test]
+    fn nes_multiplies_by_the_level() {
+        let mut rule = Nes;
+
+        assert_eq!(rule.clear_points(&clear(1, TSpin::None), 2, 0), 80);
+        assert_eq!(rule.clear_points(&clear(4, TSpin::None), 10, 0), 12000);
+        assert_eq!(rule.clear_points(&clear(2, TSpin::Full), 1, 0), 1200);
+        assert_eq!(Nes.hard_drop_points(19), 19);
+    }
+
+    #[test]
+    fn tgm_combos() {
+        let mut rule = Tgm::new();
+
+        // (ceil((10 + 2) / 4) + 0) * 2 lines * combo 3
+        assert_eq!(rule.clear_points(&clear(2, TSpin::None), 10, 0), 18);
+        // The combo stays at 3 for a single, and the soft dropped rows count before multiplying.
+        assert_eq!(rule.clear_points(&clear(1, TSpin::None), 12, 2), 18);
+
+        rule.no_clear();
+
+        assert_eq!(rule.clear_points(&clear(1, TSpin::None), 12, 0), 4);
+        assert_eq!(
+            rule.clear_points(
+                &LineClear {
+                    perfect_clear: true,
+                    ..clear(1, TSpin::None)
+                },
+                12,
+                0
+            ),
+            16
+        );
+        assert_eq!(rule.clear_points(&clear(0, TSpin::Full), 12, 0), 0);
+        assert_eq!(rule.hard_drop_points(19), 0);
+    }
+
+    #[test]
+    fn tgm_grades() {
+        let rule = Tgm::new();
+
+        assert_eq!(rule.grade(0), Some("9"));
+        assert_eq!(rule.grade(15_999), Some("1"));
+        assert_eq!(rule.grade(16_000), Some("S1"));
+        assert_eq!(rule.grade(1_000_000), Some("S9"));
+        assert_eq!(Guideline.grade(1_000_000), None);
+    }
+
+    #[test]
+    fn custom_table() {
+        let mut rule = Custom(ScoringTable {
+            lines: [1, 2, 3, 4],
+            t_spin_mini: [10, 20, 30],
+            back_to_back: 2.0,
+            combo: 5,
+            perfect_clear: [100, 200, 300, 400],
+            hard_drop: 3,
+            level_multiplier: false,
+            ..ScoringTable::default()
+        });
+        let tetris = LineClear {
+            back_to_back: true,
+            combo: 2,
+            perfect_clear: true,
+            ..clear(4, TSpin::None)
+        };
+
+        assert_eq!(rule.clear_points(&tetris, 5, 0), 4 * 2 + 400 + 2 * 5);
+        assert_eq!(rule.clear_points(&clear(2, TSpin::Mini), 5, 0), 30);
+        assert_eq!(rule.hard_drop_points(19), 57);
+    }
+
+    #[test]
+    fn default_custom_table_scores_like_the_guideline() {
+        let mut custom = Custom(ScoringTable::default());
+        let clears = [
+            clear(1, TSpin::None),
+            clear(3, TSpin::Full),
+            LineClear {
+                back_to_back: true,
+                combo: 4,
+                ..clear(4, TSpin::None)
+            },
+            LineClear {
+                perfect_clear: true,
+                ..clear(2, TSpin::None)
+            },
+        ];
+
+        for clear in &clears {
+            assert_eq!(
+                custom.clear_points(clear, 3, 0),
+                Guideline.clear_points(clear, 3, 0)
+            );
+        }
+    }
+}
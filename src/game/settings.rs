@@ -0,0 +1,114 @@
+use super::config::{setting_name, Config};
+use super::engine::{ms_to_frames, FRAMES_PER_SECOND};
+use super::randomizer::RandomizerKind;
+
+// The longest DAS and ARR the settings menu goes up to, in frames.
+const MAX_DAS_FRAMES: i64 = 30;
+const MAX_ARR_FRAMES: i64 = 10;
+const MAX_SOFT_DROP_FACTOR: i64 = 40;
+const MAX_BAG_AMOUNT: i64 = 20;
+
+/// The settings that can be changed in the settings menu, everything else is only in the config file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    ColoredBoard,
+    Randomizer,
+    BagAmount,
+    Holding,
+    Das,
+    Arr,
+    SoftDropFactor,
+}
+
+impl Setting {
+    pub const ALL: [Self; 7] = [
+        Self::ColoredBoard,
+        Self::Randomizer,
+        Self::BagAmount,
+        Self::Holding,
+        Self::Das,
+        Self::Arr,
+        Self::SoftDropFactor,
+    ];
+
+    /// The names of the settings in the config file that the settings menu writes back,
+    /// the key bindings included.
+    pub const FIELDS: [&'static str; 8] = [
+        "colored_board",
+        "randomizer",
+        "bag_amount",
+        "holding_enabled",
+        "das",
+        "arr",
+        "soft_drop_factor",
+        "controls",
+    ];
+
+    /// The name of the setting in the settings menu.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ColoredBoard => "COLORED BOARD",
+            Self::Randomizer => "RANDOMIZER",
+            Self::BagAmount => "BAG AMOUNT",
+            Self::Holding => "HOLD",
+            Self::Das => "DAS",
+            Self::Arr => "ARR",
+            Self::SoftDropFactor => "SOFT DROP",
+        }
+    }
+
+    /// The current value of the setting, the way the settings menu shows it.
+    pub fn value(self, config: &Config) -> String {
+        match self {
+            Self::ColoredBoard => on_off(config.colored_board),
+            Self::Randomizer => setting_name(&config.randomizer).to_uppercase(),
+            Self::BagAmount => config.bag_amount.to_string(),
+            Self::Holding => on_off(config.holding_enabled),
+            Self::Das => format!("{} MS ({} F)", config.das, ms_to_frames(config.das)),
+            Self::Arr => format!("{} MS ({} F)", config.arr, ms_to_frames(config.arr)),
+            Self::SoftDropFactor => format!("{}X", config.soft_drop_factor),
+        }
+    }
+
+    /// Changes the setting by a step up or down, switches flip no matter the direction.
+    /// DAS and ARR change by a whole frame at a time, since the game rounds them to frames anyway.
+    pub fn change(self, config: &mut Config, up: bool) {
+        let step = if up { 1 } else { -1 };
+
+        match self {
+            Self::ColoredBoard => config.colored_board = !config.colored_board,
+            Self::Randomizer => {
+                let index = RandomizerKind::ALL
+                    .iter()
+                    .position(|&r| r == config.randomizer)
+                    .unwrap_or(0) as i64;
+                let count = RandomizerKind::ALL.len() as i64;
+
+                config.randomizer = RandomizerKind::ALL[(index + step).rem_euclid(count) as usize];
+            }
+            Self::BagAmount => {
+                config.bag_amount =
+                    (i64::from(config.bag_amount) + step).clamp(1, MAX_BAG_AMOUNT) as u8;
+            }
+            Self::Holding => config.holding_enabled = !config.holding_enabled,
+            Self::Das => config.das = step_frames(config.das, step, MAX_DAS_FRAMES),
+            Self::Arr => config.arr = step_frames(config.arr, step, MAX_ARR_FRAMES),
+            Self::SoftDropFactor => {
+                config.soft_drop_factor = (i64::from(config.soft_drop_factor) + step)
+                    .clamp(1, MAX_SOFT_DROP_FACTOR)
+                    as u32;
+            }
+        }
+    }
+}
+
+fn on_off(value: bool) -> String {
+    if value { "ON" } else { "OFF" }.to_string()
+}
+
+/// Moves a duration in milliseconds to the next or previous whole frame.
+fn step_frames(ms: u32, step: i64, max_frames: i64) -> u32 {
+    let frames = (i64::from(ms_to_frames(ms)) + step).clamp(0, max_frames) as u32;
+
+    (frames * 1000 + FRAMES_PER_SECOND / 2) / FRAMES_PER_SECOND
+}
@@ -0,0 +1,19 @@
+//! A Tetris Clone, written in Rust using the ggez library.
+//!
+//! The game logic lives in [`Engine`], which does not need a window at all,
+//! so other crates can use it for bots, tools or their own frontends.
+//! The window version of the game is started with `run`, which needs the `gui` feature that is on by default.
+
+pub mod game;
+
+pub use game::board::Board;
+pub use game::config::{load_config, read_config, Config, ConfigError, CONFIG_VERSION};
+pub use game::controls::{Control, Controls, ControlsPreset};
+pub use game::engine::{Engine, FRAMES_PER_SECOND};
+#[cfg(feature = "gui")]
+pub use game::frontend::run;
+pub use game::highscores::{HighScore, HighScores};
+pub use game::input::Action;
+pub use game::mode::GameMode;
+pub use game::pieces::{Piece, PieceType};
+pub use game::replay::{Playback, Recorder, Replay, ReplayFormat};
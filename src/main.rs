@@ -1,5 +1,3 @@
-mod game;
-
 fn main() {
-    game::game::run().unwrap();
+    tetris_rust::run().unwrap();
 }